@@ -1,183 +1,460 @@
-use core::ops::Mul;
-
+use crate::battery::*;
 use crate::game::Minesweeper;
+use crate::i2c_scan::I2cScan;
+use crate::input::Gesture;
+use crate::macros::*;
+use crate::power::*;
+use crate::relay::*;
+use crate::screens::*;
+use crate::sniffer::*;
+use crate::storage::{Record, Storage};
+use crate::text_entry::*;
+use crate::timers::*;
 use crate::ui::*;
-use curio_bsp::hal::flash::FlashPage;
 use curio_bsp::protocol::nec::NecCommand;
 use curio_bsp::Button;
-use klaptik::*;
 
 pub enum AppEvent {
-    ClockTick,
-    Button(Button),
-    IrCommand(NecCommand),
+    ClockTick(u32),
+    Gesture(Gesture),
+    IrCommand(NecCommand, u32),
+    TimerExpired(TimerId),
+    BatterySample(u16),
+    TransmitDone,
+    I2cScanned(u128),
+    StopTimeout,
 }
 
 pub enum AppRequest {
-    SwitchOff,
+    SwitchOff(Option<Options>),
     SetBrightness(u8),
+    SetContrast(u8),
+    SetInverted(bool),
+    SetFlipped(bool),
     TransmitIRCommand(NecCommand),
     StoreOptions(Options),
+    StoreMacro(usize, Macro),
+    StoreRelayTable(RelayTable),
+    StoreLog,
+    EnterStop(Option<u32>),
+    Resume,
+    StartTimer(TimerId, TimerMode),
+    CancelTimer(TimerId),
+    ScanI2c,
 }
 
 pub struct App {
     pub frame: u8,
+    pub clock_ms: u32,
+    pub clock: Option<u32>,
+    pub clock_generation: u8,
     pub sleep_timeout: u32,
-    pub battery_voltage: Glyph,
+    pub standby: bool,
+    pub stopped: bool,
+    pub watching: bool,
+    pub dimmed: bool,
+    pub swallow: bool,
+    pub dirty: bool,
+    pub transmitting: bool,
+    pub options_dirty: bool,
+    pub repaint: bool,
+    pub locked: bool,
+    pub battery: Battery,
     pub options: Options,
     pub game: Minesweeper,
-    pub active_widget: ViewportNode,
+    pub macros: Macros,
+    pub timer: MacroTimer,
+    pub relay: Relay,
+    pub sniffer: Sniffer,
+    pub text_entry: TextEntry,
+    pub nav: NavStack,
     pub tx_cmd: NecCommand,
     pub rx_cmd: NecCommand,
-    pub address_edit: bool,
-    pub main_menu: Menu,
-    pub config_menu: Menu,
+    pub send_cursor: u8,
+    pub menu: Menu,
+    pub i2c_scan: I2cScan,
+    pub i2c_view: ListView,
 }
 
 impl App {
-    pub fn new(options: Options, battery_voltage: u16) -> Self {
-        let main_menu = Menu::new(&[MenuItem::Scan, MenuItem::Send, MenuItem::Config]);
-        let config_menu = Menu::new(&[MenuItem::About, MenuItem::Sleep, MenuItem::Backlight]);
+    pub const TICK_MS: u32 = 100;
+    pub const IDLE_TICK_MS: u32 = 1_000;
+    // Ticks to stay awake after an IR frame woke the device from watch mode.
+    pub const WATCH_GRACE: u32 = 10;
+    pub const DIM_BRIGHTNESS: u8 = 1;
+
+    pub fn new(options: Options, battery_voltage: u16, store: &mut dyn Storage) -> Self {
         let cmd = NecCommand {
             addr: 0,
             cmd: 0,
             repeat: false,
         };
-
-        let battery_voltage = battery_voltage.mul(3).saturating_sub(2200) / 200;
-        let battery_voltage = battery_voltage.clamp(0, 4) as _;
         let game = Minesweeper::new(8);
         Self {
-            main_menu,
-            config_menu,
-            battery_voltage,
+            menu: Menu::new(MAIN_MENU),
+            i2c_scan: I2cScan::new(),
+            i2c_view: ListView::new(I2cScan::ROWS),
+            battery: Battery::new(battery_voltage, options.chemistry),
             options,
             game,
+            macros: Macros::new(MacroStore::load(store)),
+            timer: MacroTimer::new(),
+            relay: Relay::new(RelayTable::load(store)),
+            sniffer: Sniffer::load(store),
+            text_entry: TextEntry::new(NameTarget::Macro(0), ""),
             frame: 0,
+            clock_ms: 0,
+            clock: None,
+            clock_generation: 0,
             tx_cmd: cmd,
             rx_cmd: cmd,
             sleep_timeout: 0,
-            address_edit: false,
-            active_widget: ViewportNode::MainMenu,
+            standby: false,
+            stopped: false,
+            watching: false,
+            dimmed: false,
+            swallow: false,
+            dirty: true,
+            transmitting: false,
+            options_dirty: false,
+            repaint: false,
+            locked: false,
+            send_cursor: 0,
+            nav: NavStack::new(ViewportNode::Menu),
+        }
+    }
+
+    pub fn active_widget(&self) -> ViewportNode {
+        self.nav.current()
+    }
+
+    pub fn open(&mut self, widget: ViewportNode) {
+        self.nav.push(widget);
+    }
+
+    pub fn back(&mut self) {
+        self.nav.pop();
+    }
+
+    pub fn home(&mut self) -> Option<AppRequest> {
+        self.nav.reset();
+        self.menu.reset();
+        self.save_options()
+    }
+
+    pub fn invalidate(&mut self) {
+        self.dirty = true;
+    }
+
+    pub fn take_dirty(&mut self) -> bool {
+        core::mem::take(&mut self.dirty)
+    }
+
+    // Redraws every widget, not just the ones whose state changed.
+    pub fn repaint(&mut self) {
+        self.repaint = true;
+        self.dirty = true;
+    }
+
+    pub fn take_repaint(&mut self) -> bool {
+        core::mem::take(&mut self.repaint)
+    }
+
+    pub fn resume(&mut self) -> Option<AppRequest> {
+        if !self.stopped {
+            return None;
+        }
+        self.stopped = false;
+        self.sleep_timeout = 0;
+        Some(AppRequest::Resume)
+    }
+
+    // The clock runs at full rate only while a macro plays or records; idle
+    // timeouts and the macro timer are fine with a coarse tick.
+    fn clock_period(&self) -> Option<u32> {
+        if self.stopped {
+            None
+        } else if self.macros.state() != MacroState::Idle {
+            Some(Self::TICK_MS)
+        } else {
+            Some(Self::IDLE_TICK_MS)
         }
     }
 
-    pub fn switch_to(&mut self, widget: ViewportNode) {
-        self.active_widget = widget;
+    pub fn schedule_clock(&mut self) -> Option<u32> {
+        self.clock = self.clock_period();
+        self.clock
+    }
+
+    // A new clock chain is due when none is running or the running one is
+    // too slow. Returns its generation and period; the chain only takes over
+    // once `start_clock` confirms it was spawned.
+    pub fn restart_clock(&self) -> Option<(u8, u32)> {
+        let period = self.clock_period()?;
+        if self.clock.is_some_and(|current| current <= period) {
+            return None;
+        }
+        Some((self.clock_generation.wrapping_add(1), period))
+    }
+
+    // Any older chain sees a stale generation and ends.
+    pub fn start_clock(&mut self, generation: u8, period: u32) {
+        self.clock_generation = generation;
+        self.clock = Some(period);
+    }
+
+    fn can_stop(&self) -> bool {
+        !self.stopped
+            && !self.timer.is_armed()
+            && self.macros.state() == MacroState::Idle
+            && (self.active_widget() != ViewportNode::Relay || self.options.ir_wake)
+    }
+
+    pub fn load_ma(&self) -> u16 {
+        let mut load = 0;
+        if !self.standby {
+            load += self.options.backlight as u16 * Battery::BACKLIGHT_MA_PER_STEP;
+        }
+        if self.transmitting {
+            load += Battery::IR_TX_MA;
+        }
+        load
     }
 
     pub fn handle_event(&mut self, ev: AppEvent) -> Option<AppRequest> {
-        match ev {
-            AppEvent::ClockTick => {
-                self.frame = self.frame.wrapping_add(1);
-                self.sleep_timeout = self.sleep_timeout.wrapping_add(1);
-                if self.sleep_timeout / 10 > self.options.sleep_timeout as _ {
-                    Some(AppRequest::SwitchOff)
-                } else {
-                    None
-                }
-            }
-            AppEvent::IrCommand(cmd) => {
-                self.rx_cmd = cmd;
-                None
-            }
-            AppEvent::Button(btn) => self.handle_button(btn),
+        let req = self.dispatch(ev);
+        match req {
+            Some(AppRequest::TransmitIRCommand(_)) => self.transmitting = true,
+            Some(AppRequest::StoreOptions(_)) => self.options_dirty = false,
+            _ => {}
         }
+        req
     }
 
-    fn handle_button(&mut self, btn: Button) -> Option<AppRequest> {
-        self.sleep_timeout = 0;
+    // Options edited in place are written once, when their screen is left.
+    pub fn save_options(&mut self) -> Option<AppRequest> {
+        self.options_dirty
+            .then_some(AppRequest::StoreOptions(self.options))
+    }
 
-        match self.active_widget {
-            ViewportNode::Game => self.game.button_click(btn),
-            ViewportNode::MainMenu => match btn {
-                Button::A => match self.main_menu.selected() {
-                    MenuItem::Config => self.switch_to(ViewportNode::ConfigMenu),
-                    MenuItem::Scan => self.switch_to(ViewportNode::Scan),
-                    MenuItem::Send => self.switch_to(ViewportNode::Send),
-                    _ => {}
-                },
-                Button::B => return Some(AppRequest::SwitchOff),
-                Button::Up => self.main_menu.move_up(),
-                Button::Down => self.main_menu.move_down(),
-                _ => {}
-            },
-            ViewportNode::Scan => match btn {
-                Button::A => return Some(AppRequest::TransmitIRCommand(self.rx_cmd)),
-                Button::B => self.switch_to(ViewportNode::MainMenu),
-                _ => {}
-            },
-            ViewportNode::Send => match btn {
-                Button::A => return Some(AppRequest::TransmitIRCommand(self.tx_cmd)),
-                Button::B => self.switch_to(ViewportNode::MainMenu),
-                Button::Right => self.address_edit = false,
-                Button::Left => self.address_edit = true,
-                Button::Up if self.address_edit => {
-                    self.tx_cmd.addr = self.tx_cmd.addr.wrapping_add(1)
+    // The log is written out when its screen is left and at power-off,
+    // not on every frame.
+    pub fn save_log(&mut self) -> Option<AppRequest> {
+        self.sniffer.take_unsaved().then_some(AppRequest::StoreLog)
+    }
+
+    // Nothing runs after power-off, so unsaved options go out with it.
+    pub fn switch_off(&mut self) -> Option<AppRequest> {
+        let options = self.options_dirty.then_some(self.options);
+        self.options_dirty = false;
+        Some(AppRequest::SwitchOff(options))
+    }
+
+    fn dispatch(&mut self, ev: AppEvent) -> Option<AppRequest> {
+        match ev {
+            AppEvent::ClockTick(now_ms) => {
+                let ticks = now_ms.wrapping_sub(self.clock_ms) / Self::TICK_MS;
+                if ticks == 0 {
+                    return None;
                 }
-                Button::Up if !self.address_edit => {
-                    self.tx_cmd.cmd = self.tx_cmd.cmd.wrapping_add(1)
+                self.clock_ms = self.clock_ms.wrapping_add(ticks * Self::TICK_MS);
+                self.frame = self.frame.wrapping_add(ticks as u8);
+                if self.timer.is_armed() || self.macros.state() != MacroState::Idle {
+                    self.invalidate();
                 }
-                Button::Down if self.address_edit => {
-                    self.tx_cmd.addr = self.tx_cmd.addr.wrapping_sub(1);
+                if self.active_widget() == ViewportNode::Relay && !self.options.ir_wake {
+                    self.sleep_timeout = 0;
                 }
-                Button::Down if !self.address_edit => {
-                    self.tx_cmd.cmd = self.tx_cmd.cmd.wrapping_sub(1);
+                if let Some(slot) = self.timer.tick(ticks) {
+                    self.macros.play_slot(slot);
                 }
-                _ => {}
-            },
-            ViewportNode::ConfigMenu => match btn {
-                Button::A => match self.config_menu.selected() {
-                    MenuItem::Backlight => self.switch_to(ViewportNode::Backlight),
-                    MenuItem::Sleep => self.switch_to(ViewportNode::SleepTimeout),
-                    MenuItem::About => self.switch_to(ViewportNode::About),
-                    _ => {}
-                },
-                Button::B => self.switch_to(ViewportNode::MainMenu),
-                Button::Up => self.config_menu.move_up(),
-                Button::Down => self.config_menu.move_down(),
-
-                _ => {}
-            },
-            ViewportNode::Backlight => match btn {
-                Button::A => {
-                    self.switch_to(ViewportNode::ConfigMenu);
-                    return Some(AppRequest::StoreOptions(self.options));
+                if let Some(cmd) = self.macros.tick() {
+                    self.sleep_timeout = 0;
+                    return Some(AppRequest::TransmitIRCommand(cmd));
                 }
-                Button::B => self.switch_to(ViewportNode::ConfigMenu),
-                Button::Up => {
-                    self.options.backlight = self.options.backlight.saturating_add(1).clamp(0, 10);
-                    return Some(AppRequest::SetBrightness(self.options.backlight));
+                if self.macros.state() != MacroState::Idle {
+                    self.sleep_timeout = 0;
                 }
-                Button::Down => {
-                    self.options.backlight = self.options.backlight.saturating_sub(1);
-                    return Some(AppRequest::SetBrightness(self.options.backlight));
+                self.sleep_timeout = self.sleep_timeout.wrapping_add(ticks);
+                let idle_secs = self.sleep_timeout / 10;
+                if self.watching {
+                    if self.sleep_timeout > Self::WATCH_GRACE && self.can_stop() {
+                        self.stopped = true;
+                        return Some(AppRequest::EnterStop(None));
+                    }
+                    None
+                } else if idle_secs <= self.options.sleep_timeout as _ {
+                    let dim_timeout = self.options.dim_timeout as u32;
+                    if !self.standby && !self.dimmed && dim_timeout > 0 && idle_secs > dim_timeout {
+                        self.dimmed = true;
+                        let brightness = Self::DIM_BRIGHTNESS.min(self.options.backlight);
+                        return Some(AppRequest::SetBrightness(brightness));
+                    }
+                    match self.options.power.stop_after() {
+                        Some(secs) if idle_secs > secs && self.can_stop() => {
+                            self.standby = true;
+                            self.stopped = true;
+                            // The clock halts in Stop, so the rest of the idle
+                            // timeout is left to the RTC wakeup.
+                            let remaining = self.options.sleep_timeout as u32 + 1 - idle_secs;
+                            Some(AppRequest::EnterStop(Some(remaining)))
+                        }
+                        _ => None,
+                    }
+                } else if !self.timer.is_armed() && self.options.ir_wake {
+                    self.standby = true;
+                    self.stopped = true;
+                    self.watching = true;
+                    Some(AppRequest::EnterStop(None))
+                } else if !self.timer.is_armed() {
+                    self.switch_off()
+                } else if !self.standby {
+                    self.standby = true;
+                    Some(AppRequest::SetBrightness(0))
+                } else {
+                    None
                 }
-                _ => {}
-            },
-            ViewportNode::SleepTimeout => match btn {
-                Button::A => {
-                    self.switch_to(ViewportNode::ConfigMenu);
-                    return Some(AppRequest::StoreOptions(self.options));
+            }
+            AppEvent::IrCommand(cmd, now_ms) => {
+                self.invalidate();
+                if self.active_widget() != ViewportNode::Relay {
+                    self.rx_cmd = cmd;
+                    self.sniffer.record(now_ms / Self::TICK_MS, cmd);
+                    self.macros.record_command(cmd);
+                    return None;
                 }
-                Button::B => self.switch_to(ViewportNode::ConfigMenu),
-                Button::Up => {
-                    self.options.sleep_timeout =
-                        self.options.sleep_timeout.saturating_add(5).clamp(10, 90)
+                if !self.relay.is_learning() {
+                    return self
+                        .relay
+                        .forward(cmd, now_ms)
+                        .map(AppRequest::TransmitIRCommand);
                 }
-                Button::Down => {
-                    self.options.sleep_timeout =
-                        self.options.sleep_timeout.saturating_sub(5).clamp(10, 90)
+                if !cmd.repeat && self.relay.learn(cmd) {
+                    return Some(AppRequest::StoreRelayTable(*self.relay.table()));
                 }
-                _ => {}
-            },
-            ViewportNode::About => match btn {
-                Button::A => self.switch_to(ViewportNode::Game),
-                Button::B => self.switch_to(ViewportNode::ConfigMenu),
-                _ => {}
-            },
+                None
+            }
+            AppEvent::TimerExpired(id) => {
+                self.invalidate();
+                match id {
+                    TimerId::RelayLearn => self.relay.stop_learning(),
+                }
+                None
+            }
+            AppEvent::BatterySample(sample) => {
+                let glyph = self.battery.glyph();
+                let alert = self.battery.sample(sample, self.load_ma());
+                let shows_voltage = matches!(
+                    self.active_widget(),
+                    ViewportNode::Battery | ViewportNode::BatteryWarning
+                );
+                if glyph != self.battery.glyph() || shows_voltage {
+                    self.invalidate();
+                }
+                match alert {
+                    Some(BatteryAlert::Critical) => self.switch_off(),
+                    Some(BatteryAlert::Low) => {
+                        if self.active_widget() != ViewportNode::BatteryWarning {
+                            self.open(ViewportNode::BatteryWarning);
+                            self.invalidate();
+                        }
+                        None
+                    }
+                    None => None,
+                }
+            }
+            AppEvent::TransmitDone => {
+                self.transmitting = false;
+                None
+            }
+            AppEvent::I2cScanned(found) => {
+                self.invalidate();
+                self.i2c_scan.finish(found);
+                None
+            }
+            AppEvent::StopTimeout => {
+                if !self.stopped || self.watching {
+                    return None;
+                }
+                if self.options.ir_wake {
+                    self.watching = true;
+                    return None;
+                }
+                self.switch_off()
+            }
+            AppEvent::Gesture(gesture) => {
+                self.invalidate();
+                self.handle_gesture(gesture)
+            }
+        }
+    }
+
+    fn handle_gesture(&mut self, gesture: Gesture) -> Option<AppRequest> {
+        if let Gesture::Combo(Button::A, Button::B) | Gesture::Combo(Button::B, Button::A) = gesture
+        {
+            self.locked = !self.locked;
+            self.sleep_timeout = 0;
+            if self.standby || self.dimmed {
+                self.standby = false;
+                self.watching = false;
+                self.dimmed = false;
+                return Some(AppRequest::SetBrightness(self.options.backlight));
+            }
+            return None;
+        }
+
+        if self.locked {
+            return None;
+        }
+
+        self.sleep_timeout = 0;
+
+        // The press that brings the screen back is swallowed, together with
+        // anything that follows from it up to its release. A and B only click
+        // on release, so holding one wakes on its long press instead.
+        if self.standby || self.dimmed {
+            if let Gesture::Press(_) | Gesture::LongPress(_) = gesture {
+                self.standby = false;
+                self.watching = false;
+                self.dimmed = false;
+                self.swallow = true;
+                return Some(AppRequest::SetBrightness(self.options.backlight));
+            }
+            return None;
+        }
+        if self.swallow {
+            if let Gesture::Release(_) = gesture {
+                self.swallow = false;
+            }
+            return None;
+        }
+
+        let screen = screen(self.active_widget());
+        match gesture {
+            Gesture::Press(Button::B) | Gesture::DoubleClick(Button::B) => screen.handle_back(self),
+            Gesture::Press(btn) | Gesture::DoubleClick(btn) => screen.handle_button(self, btn, 1),
+            Gesture::Repeat(Button::A | Button::B, _) => None,
+            Gesture::Repeat(btn, step) if screen.accepts_repeat() => {
+                screen.handle_button(self, btn, step)
+            }
+            Gesture::LongPress(btn) => screen.handle_long_press(self, btn),
+            Gesture::Combo(_, _) | Gesture::Repeat(_, _) | Gesture::Release(_) => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Radix {
+    Dec = 0,
+    Hex = 1,
+}
+
+impl Radix {
+    pub fn toggle(self) -> Self {
+        match self {
+            Radix::Dec => Radix::Hex,
+            Radix::Hex => Radix::Dec,
         }
-        None
     }
 }
 
@@ -185,21 +462,66 @@ impl App {
 pub struct Options {
     pub backlight: u8,
     pub sleep_timeout: u8,
+    pub radix: Radix,
+    pub chemistry: Chemistry,
+    pub power: PowerProfile,
+    pub ir_wake: bool,
+    pub dim_timeout: u8,
+    pub contrast: u8,
+    pub invert: bool,
+    pub flipped: bool,
 }
 
 impl Options {
-    pub const PAGE: FlashPage = FlashPage(31);
+    pub const SIZE: usize = 10;
+    pub const DIM_STEP: u8 = 5;
+    pub const MAX_DIM_TIMEOUT: u8 = 60;
+    // Below this the ST7567 shows nothing readable, and a blank screen
+    // cannot be used to bring the contrast back up.
+    pub const MIN_CONTRAST: u8 = 16;
+    pub const MAX_CONTRAST: u8 = 63;
+    pub const DEFAULT_CONTRAST: u8 = 31;
 
-    pub fn load() -> Self {
-        let opts = unsafe { core::ptr::read(Self::PAGE.to_address() as *const u16) };
-        let [backlight, sleep_timeout] = opts.to_le_bytes();
+    pub fn load(store: &mut dyn Storage) -> Self {
+        let mut opts = [0; Self::SIZE];
+        store.read(Record::Options, 0, &mut opts);
+        let [backlight, sleep_timeout, radix, chemistry, power, ir_wake, dim_timeout, contrast, invert, flipped] =
+            opts;
+        let radix = if radix == Radix::Hex as u8 {
+            Radix::Hex
+        } else {
+            Radix::Dec
+        };
         Self {
             backlight: backlight.clamp(0, 10),
             sleep_timeout: sleep_timeout.clamp(10, 60),
+            radix,
+            chemistry: Chemistry::from_byte(chemistry),
+            power: PowerProfile::from_byte(power),
+            ir_wake: ir_wake == 1,
+            dim_timeout: dim_timeout.min(Self::MAX_DIM_TIMEOUT),
+            contrast: if (Self::MIN_CONTRAST..=Self::MAX_CONTRAST).contains(&contrast) {
+                contrast
+            } else {
+                Self::DEFAULT_CONTRAST
+            },
+            invert: invert == 1,
+            flipped: flipped == 1,
         }
     }
 
-    pub fn into_bytes(self) -> [u8; 2] {
-        [self.backlight, self.sleep_timeout]
+    pub fn into_bytes(self) -> [u8; Self::SIZE] {
+        [
+            self.backlight,
+            self.sleep_timeout,
+            self.radix as u8,
+            self.chemistry as u8,
+            self.power as u8,
+            self.ir_wake as u8,
+            self.dim_timeout,
+            self.contrast,
+            self.invert as u8,
+            self.flipped as u8,
+        ]
     }
 }
@@ -0,0 +1,151 @@
+use klaptik::Glyph;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Chemistry {
+    CoinCell = 0,
+    Alkaline = 1,
+}
+
+impl Chemistry {
+    pub fn from_byte(byte: u8) -> Self {
+        if byte == Chemistry::Alkaline as u8 {
+            Chemistry::Alkaline
+        } else {
+            Chemistry::CoinCell
+        }
+    }
+
+    pub fn toggle(self) -> Self {
+        match self {
+            Chemistry::CoinCell => Chemistry::Alkaline,
+            Chemistry::Alkaline => Chemistry::CoinCell,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Chemistry::CoinCell => "CR2032",
+            Chemistry::Alkaline => "2x AAA",
+        }
+    }
+
+    // Open-circuit voltage to state of charge, highest voltage first.
+    fn discharge_curve(&self) -> &'static [(u16, u8)] {
+        match self {
+            Chemistry::CoinCell => &[
+                (3_000, 100),
+                (2_900, 80),
+                (2_800, 60),
+                (2_700, 40),
+                (2_600, 25),
+                (2_500, 15),
+                (2_400, 8),
+                (2_200, 2),
+                (2_000, 0),
+            ],
+            Chemistry::Alkaline => &[
+                (3_200, 100),
+                (3_000, 85),
+                (2_800, 70),
+                (2_600, 45),
+                (2_400, 25),
+                (2_200, 10),
+                (2_000, 0),
+            ],
+        }
+    }
+
+    fn internal_resistance_mohm(&self) -> u32 {
+        match self {
+            Chemistry::CoinCell => 15_000,
+            Chemistry::Alkaline => 300,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BatteryAlert {
+    Low,
+    Critical,
+}
+
+pub struct Battery {
+    chemistry: Chemistry,
+    millivolts: u16,
+    warned: bool,
+}
+
+impl Battery {
+    pub const SAMPLE_MS: u32 = 5_000;
+    pub const DIVIDER: u16 = 3;
+    pub const LOW_PERCENT: u8 = 10;
+    pub const HYSTERESIS_PERCENT: u8 = 5;
+    pub const BACKLIGHT_MA_PER_STEP: u16 = 1;
+    pub const IR_TX_MA: u16 = 30;
+
+    pub fn new(sample: u16, chemistry: Chemistry) -> Self {
+        Self {
+            chemistry,
+            millivolts: sample.saturating_mul(Self::DIVIDER),
+            warned: false,
+        }
+    }
+
+    pub fn chemistry(&self) -> Chemistry {
+        self.chemistry
+    }
+
+    pub fn set_chemistry(&mut self, chemistry: Chemistry) {
+        self.chemistry = chemistry;
+        self.warned = false;
+    }
+
+    pub fn millivolts(&self) -> u16 {
+        self.millivolts
+    }
+
+    pub fn percent(&self) -> u8 {
+        let curve = self.chemistry.discharge_curve();
+        let mv = self.millivolts;
+        let (top_mv, top_percent) = curve[0];
+        if mv >= top_mv {
+            return top_percent;
+        }
+        for pair in curve.windows(2) {
+            let (hi_mv, hi_percent) = pair[0];
+            let (lo_mv, lo_percent) = pair[1];
+            if mv >= lo_mv {
+                let span = (hi_percent - lo_percent) as u32;
+                let offset = (mv - lo_mv) as u32 * span / (hi_mv - lo_mv) as u32;
+                return lo_percent + offset as u8;
+            }
+        }
+        0
+    }
+
+    pub fn glyph(&self) -> Glyph {
+        ((self.percent() as u16 + 12) / 25).clamp(0, 4) as _
+    }
+
+    pub fn sample(&mut self, sample: u16, load_ma: u16) -> Option<BatteryAlert> {
+        // Undo the drop across the cell's internal resistance so readings
+        // taken under load are comparable with the open-circuit curve.
+        let sag = load_ma as u32 * self.chemistry.internal_resistance_mohm() / 1_000;
+        let millivolts = sample.saturating_mul(Self::DIVIDER) as u32 + sag;
+
+        // Exponential moving average with a weight of 1/4 for the new sample.
+        self.millivolts = ((self.millivolts as u32 * 3 + millivolts) / 4).min(u16::MAX as _) as _;
+
+        let percent = self.percent();
+        if percent == 0 {
+            return Some(BatteryAlert::Critical);
+        }
+        if percent >= Self::LOW_PERCENT + Self::HYSTERESIS_PERCENT {
+            self.warned = false;
+        } else if percent < Self::LOW_PERCENT && !self.warned {
+            self.warned = true;
+            return Some(BatteryAlert::Low);
+        }
+        None
+    }
+}
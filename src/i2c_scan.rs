@@ -0,0 +1,74 @@
+use crate::ui::ListSource;
+use core::fmt::Write;
+
+pub struct I2cScan {
+    found: u128,
+    scanning: bool,
+}
+
+impl I2cScan {
+    pub const FIRST: u8 = 0x08;
+    pub const LAST: u8 = 0x77;
+    pub const ROWS: usize = 5;
+
+    pub fn new() -> Self {
+        Self {
+            found: 0,
+            scanning: false,
+        }
+    }
+
+    pub fn probe(mut responds: impl FnMut(u8) -> bool) -> u128 {
+        (Self::FIRST..=Self::LAST)
+            .filter(|addr| responds(*addr))
+            .fold(0, |found, addr| found | 1 << addr)
+    }
+
+    pub fn is_scanning(&self) -> bool {
+        self.scanning
+    }
+
+    pub fn start(&mut self) -> bool {
+        if self.scanning {
+            return false;
+        }
+        self.scanning = true;
+        self.found = 0;
+        true
+    }
+
+    pub fn finish(&mut self, found: u128) {
+        self.scanning = false;
+        self.found = found;
+    }
+
+    pub fn address(&self, idx: usize) -> Option<u8> {
+        (Self::FIRST..=Self::LAST)
+            .filter(|addr| self.found & 1 << addr != 0)
+            .nth(idx)
+    }
+
+    pub fn part_name(addr: u8) -> &'static str {
+        match addr {
+            0x20..=0x27 => "IO expander",
+            0x3c | 0x3d => "OLED",
+            0x48..=0x4f => "Temp sensor",
+            0x50..=0x57 => "EEPROM",
+            0x68 => "RTC",
+            0x76 | 0x77 => "Baro sensor",
+            _ => "",
+        }
+    }
+}
+
+impl ListSource for I2cScan {
+    fn len(&self) -> usize {
+        self.found.count_ones() as _
+    }
+
+    fn item(&self, idx: usize, out: &mut dyn Write) {
+        if let Some(addr) = self.address(idx) {
+            write!(out, "x{:02X} {}", addr, Self::part_name(addr)).ok();
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use curio_bsp::Button;
+
+#[derive(Clone, Copy)]
+pub enum Gesture {
+    Press(Button),
+    Repeat(Button, u8),
+    LongPress(Button),
+    DoubleClick(Button),
+    Combo(Button, Button),
+    Release(Button),
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct Buttons(u8);
+
+impl Buttons {
+    const ALL: [Button; 6] = [
+        Button::A,
+        Button::B,
+        Button::Up,
+        Button::Down,
+        Button::Left,
+        Button::Right,
+    ];
+
+    pub fn with(self, btn: Option<Button>) -> Self {
+        match btn {
+            Some(btn) => Self(self.0 | Self::mask(btn)),
+            None => self,
+        }
+    }
+
+    pub fn without(self, btn: Button) -> Self {
+        Self(self.0 & !Self::mask(btn))
+    }
+
+    pub fn contains(self, btn: Button) -> bool {
+        self.0 & Self::mask(btn) != 0
+    }
+
+    pub fn len(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    // Swaps opposite D-pad directions for a device turned upside down.
+    pub fn flipped(self) -> Self {
+        let mut flipped = Self(self.0 & (Self::mask(Button::A) | Self::mask(Button::B)));
+        for (from, to) in [
+            (Button::Up, Button::Down),
+            (Button::Down, Button::Up),
+            (Button::Left, Button::Right),
+            (Button::Right, Button::Left),
+        ] {
+            if self.contains(from) {
+                flipped = flipped.with(Some(to));
+            }
+        }
+        flipped
+    }
+
+    pub fn first(self) -> Option<Button> {
+        Self::ALL.into_iter().find(|btn| self.contains(*btn))
+    }
+
+    fn mask(btn: Button) -> u8 {
+        match btn {
+            Button::A => 1,
+            Button::B => 1 << 1,
+            Button::Up => 1 << 2,
+            Button::Down => 1 << 3,
+            Button::Left => 1 << 4,
+            Button::Right => 1 << 5,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct InputConfig {
+    pub initial_delay: u32,
+    pub repeat_rate: u32,
+    pub long_press: u32,
+    pub double_click: u32,
+    pub debounce: u32,
+    pub acceleration: &'static [(u32, u8)],
+}
+
+impl InputConfig {
+    pub const DEFAULT: InputConfig = InputConfig {
+        initial_delay: 400,
+        repeat_rate: 120,
+        long_press: 800,
+        double_click: 300,
+        debounce: 40,
+        acceleration: &[(1_000, 10), (3_000, 50)],
+    };
+}
+
+pub struct Input {
+    config: InputConfig,
+    held: Buttons,
+    primary: Option<Button>,
+    held_ms: u32,
+    next_repeat: u32,
+    long_pressed: bool,
+    combo: bool,
+    last_click: Option<Button>,
+    since_release: u32,
+    period: u32,
+    click: Option<Gesture>,
+    queued: Option<Gesture>,
+    edge: Option<u32>,
+    flipped: bool,
+}
+
+impl Input {
+    pub const POLL_MS: u32 = 20;
+    pub const IDLE_POLL_MS: u32 = 150;
+
+    pub fn new(config: InputConfig) -> Self {
+        Self {
+            config,
+            held: Buttons::default(),
+            primary: None,
+            held_ms: 0,
+            next_repeat: 0,
+            long_pressed: false,
+            combo: false,
+            last_click: None,
+            since_release: u32::MAX,
+            period: Self::IDLE_POLL_MS,
+            click: None,
+            queued: None,
+            edge: None,
+            flipped: false,
+        }
+    }
+
+    pub fn set_flipped(&mut self, flipped: bool) {
+        self.flipped = flipped;
+    }
+
+    // A and B share one reader that reports a single button, so a press of
+    // the second one is only visible as an EXTI edge while the first is held.
+    // Returns the new poll period when the timer has to speed up for it.
+    pub fn wake(&mut self) -> Option<u32> {
+        self.edge = Some(0);
+        if self.period == Self::POLL_MS {
+            return None;
+        }
+        self.period = Self::POLL_MS;
+        Some(Self::POLL_MS)
+    }
+
+    // Polls fast only while a key is down or a double click may follow,
+    // returning the new period when the poll timer has to change.
+    pub fn reschedule(&mut self) -> Option<u32> {
+        let idle = self.held.is_empty() && self.since_release > self.config.double_click;
+        let period = if idle {
+            Self::IDLE_POLL_MS
+        } else {
+            Self::POLL_MS
+        };
+        if period == self.period {
+            return None;
+        }
+        self.period = period;
+        Some(period)
+    }
+
+    pub fn update(&mut self, pressed: Buttons) -> Option<Gesture> {
+        let pressed = if self.flipped {
+            pressed.flipped()
+        } else {
+            pressed
+        };
+        if let Some(gesture) = self.queued.take() {
+            return Some(gesture);
+        }
+        let pressed = self.resolve_edge(pressed);
+        self.since_release = self.since_release.saturating_add(self.period);
+
+        if pressed.is_empty() {
+            self.held = pressed;
+            let released = self.primary.take()?;
+            if self.combo {
+                self.combo = false;
+                self.last_click = None;
+                return None;
+            }
+            self.last_click = Some(released);
+            self.since_release = 0;
+            return self.release(released);
+        }
+
+        let joined = pressed.len() > self.held.len();
+        self.held = pressed;
+        if self.combo {
+            return None;
+        }
+
+        match self.primary {
+            Some(primary) if !pressed.contains(primary) => {
+                self.primary = None;
+                self.release(primary)
+            }
+            Some(primary) if joined => {
+                self.combo = true;
+                self.click = None;
+                pressed
+                    .without(primary)
+                    .first()
+                    .map(|second| Gesture::Combo(primary, second))
+            }
+            Some(primary) => self.hold(primary),
+            None => {
+                let btn = pressed.first()?;
+                self.primary = Some(btn);
+                self.held_ms = 0;
+                self.next_repeat = self.config.initial_delay;
+                self.long_pressed = false;
+                if let Some(second) = pressed.without(btn).first() {
+                    self.combo = true;
+                    return Some(Gesture::Combo(btn, second));
+                }
+                let click = if self.last_click.take() == Some(btn)
+                    && self.since_release <= self.config.double_click
+                {
+                    Gesture::DoubleClick(btn)
+                } else {
+                    Gesture::Press(btn)
+                };
+                // A and B click on release, so holding one for a long press
+                // or a combo never fires its action first.
+                if let Button::A | Button::B = btn {
+                    self.click = Some(click);
+                    return None;
+                }
+                Some(click)
+            }
+        }
+    }
+
+    fn release(&mut self, btn: Button) -> Option<Gesture> {
+        match self.click.take() {
+            Some(click) => {
+                self.queued = Some(Gesture::Release(btn));
+                Some(click)
+            }
+            None => Some(Gesture::Release(btn)),
+        }
+    }
+
+    // Contact bounce on the held key's own press or release is an edge too,
+    // so only one that comes well into a hold and outlasts the debounce
+    // window with the level unchanged counts as the other key.
+    fn resolve_edge(&mut self, pressed: Buttons) -> Buttons {
+        let Some(age) = self.edge else {
+            return pressed;
+        };
+        if pressed != self.held || self.held_ms < self.config.debounce {
+            self.edge = None;
+            return pressed;
+        }
+        let age = age + self.period;
+        if age < self.config.debounce {
+            self.edge = Some(age);
+            return pressed;
+        }
+        self.edge = None;
+        match self.primary {
+            Some(Button::A) if pressed.contains(Button::A) => pressed.with(Some(Button::B)),
+            Some(Button::B) if pressed.contains(Button::B) => pressed.with(Some(Button::A)),
+            _ => pressed,
+        }
+    }
+
+    fn hold(&mut self, btn: Button) -> Option<Gesture> {
+        self.held_ms += self.period;
+        if !self.long_pressed && self.held_ms >= self.config.long_press {
+            self.long_pressed = true;
+            self.click = None;
+            return Some(Gesture::LongPress(btn));
+        }
+        if self.held_ms >= self.next_repeat {
+            self.next_repeat += self.config.repeat_rate;
+            return Some(Gesture::Repeat(btn, self.step()));
+        }
+        None
+    }
+
+    fn step(&self) -> u8 {
+        self.config
+            .acceleration
+            .iter()
+            .rev()
+            .find(|(after, _)| self.held_ms >= *after)
+            .map(|(_, step)| *step)
+            .unwrap_or(1)
+    }
+}
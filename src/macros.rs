@@ -0,0 +1,392 @@
+use crate::storage::{Record, Storage};
+use curio_bsp::protocol::nec::NecCommand;
+
+#[derive(Clone, Copy)]
+pub struct MacroStep {
+    pub cmd: NecCommand,
+    pub delay: u8,
+}
+
+impl MacroStep {
+    pub const SIZE: usize = 4;
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let cmd = NecCommand {
+            addr: bytes[0],
+            cmd: bytes[1],
+            repeat: false,
+        };
+        Self {
+            cmd,
+            delay: bytes[2],
+        }
+    }
+
+    fn into_bytes(self) -> [u8; Self::SIZE] {
+        [self.cmd.addr, self.cmd.cmd, self.delay, 0]
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Macro {
+    steps: [MacroStep; Self::MAX_STEPS],
+    len: usize,
+    name: [u8; Self::NAME_LEN],
+}
+
+impl Macro {
+    pub const MAX_STEPS: usize = 8;
+    pub const HEADER_SIZE: usize = 16;
+    pub const NAME_OFFSET: usize = 4;
+    pub const NAME_LEN: usize = 12;
+    pub const SIZE: usize = Self::HEADER_SIZE + Self::MAX_STEPS * MacroStep::SIZE;
+
+    pub fn new() -> Self {
+        let step = MacroStep {
+            cmd: NecCommand {
+                addr: 0,
+                cmd: 0,
+                repeat: false,
+            },
+            delay: 0,
+        };
+        Self {
+            steps: [step; Self::MAX_STEPS],
+            len: 0,
+            name: [b' '; Self::NAME_LEN],
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut script = Self::new();
+        let len = bytes[0] as usize;
+        if len > Self::MAX_STEPS {
+            return script;
+        }
+        script.set_name(&bytes[Self::NAME_OFFSET..Self::HEADER_SIZE]);
+        let steps = bytes[Self::HEADER_SIZE..].chunks(MacroStep::SIZE);
+        for (step, bytes) in script.steps.iter_mut().zip(steps).take(len) {
+            *step = MacroStep::from_bytes(bytes);
+        }
+        script.len = len;
+        script
+    }
+
+    pub fn into_bytes(self) -> [u8; Self::SIZE] {
+        let mut bytes = [0xff; Self::SIZE];
+        bytes[0] = self.len as _;
+        bytes[Self::NAME_OFFSET..Self::HEADER_SIZE].copy_from_slice(&self.name);
+        let chunks = bytes[Self::HEADER_SIZE..].chunks_mut(MacroStep::SIZE);
+        for (chunk, step) in chunks.zip(self.steps()) {
+            chunk.copy_from_slice(&step.into_bytes());
+        }
+        bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn name(&self) -> &str {
+        core::str::from_utf8(&self.name).unwrap_or("").trim_end()
+    }
+
+    pub fn set_name(&mut self, name: &[u8]) {
+        self.name = [b' '; Self::NAME_LEN];
+        for (dst, ch) in self.name.iter_mut().zip(name) {
+            *dst = if ch.is_ascii_graphic() { *ch } else { b' ' };
+        }
+    }
+
+    pub fn steps(&self) -> &[MacroStep] {
+        &self.steps[..self.len]
+    }
+
+    pub fn push(&mut self, cmd: NecCommand) -> bool {
+        if self.len == Self::MAX_STEPS {
+            return false;
+        }
+        self.steps[self.len] = MacroStep { cmd, delay: 0 };
+        self.len += 1;
+        true
+    }
+
+    pub fn set_last_delay(&mut self, delay: u8) {
+        if let Some(step) = self.steps[..self.len].last_mut() {
+            step.delay = delay;
+        }
+    }
+
+    pub fn duration(&self) -> u32 {
+        self.steps().iter().map(|step| step.delay as u32 + 1).sum()
+    }
+}
+
+// RAM copy of the stored macros, so switching slots never touches the bus.
+pub struct MacroStore {
+    bytes: [u8; Self::SIZE],
+}
+
+impl MacroStore {
+    pub const SLOTS: usize = 4;
+    pub const SIZE: usize = Self::SLOTS * Macro::SIZE;
+
+    pub fn load(store: &mut dyn Storage) -> Self {
+        let mut bytes = [0; Self::SIZE];
+        store.read(Record::Macros, 0, &mut bytes);
+        Self { bytes }
+    }
+
+    pub fn get(&self, slot: usize) -> Macro {
+        Macro::from_bytes(&self.bytes[slot * Macro::SIZE..])
+    }
+
+    pub fn set(&mut self, slot: usize, script: Macro) {
+        self.bytes[slot * Macro::SIZE..][..Macro::SIZE].copy_from_slice(&script.into_bytes());
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MacroState {
+    Idle,
+    Playing,
+    Recording,
+}
+
+pub struct Macros {
+    store: MacroStore,
+    slot: usize,
+    script: Macro,
+    state: MacroState,
+    step: usize,
+    countdown: u8,
+    elapsed: u32,
+}
+
+impl Macros {
+    pub fn new(store: MacroStore) -> Self {
+        Self {
+            script: store.get(0),
+            store,
+            slot: 0,
+            state: MacroState::Idle,
+            step: 0,
+            countdown: 0,
+            elapsed: 0,
+        }
+    }
+
+    pub fn slot(&self) -> usize {
+        self.slot
+    }
+
+    pub fn script(&self) -> &Macro {
+        &self.script
+    }
+
+    pub fn state(&self) -> MacroState {
+        self.state
+    }
+
+    pub fn select_next(&mut self) {
+        self.select((self.slot + 1) % MacroStore::SLOTS);
+    }
+
+    pub fn select_prev(&mut self) {
+        self.select((self.slot + MacroStore::SLOTS - 1) % MacroStore::SLOTS);
+    }
+
+    fn select(&mut self, slot: usize) {
+        if self.state == MacroState::Idle {
+            self.slot = slot;
+            self.script = self.store.get(slot);
+        }
+    }
+
+    pub fn play_slot(&mut self, slot: usize) {
+        self.cancel();
+        self.select(slot);
+        self.play();
+    }
+
+    pub fn play(&mut self) {
+        if self.state == MacroState::Idle && !self.script.is_empty() {
+            self.state = MacroState::Playing;
+            self.step = 0;
+            self.countdown = 0;
+            self.elapsed = 0;
+        }
+    }
+
+    pub fn record(&mut self) {
+        if self.state == MacroState::Idle {
+            self.state = MacroState::Recording;
+            self.script = Macro::new();
+            self.elapsed = 0;
+        }
+    }
+
+    pub fn record_command(&mut self, cmd: NecCommand) {
+        if self.state != MacroState::Recording || cmd.repeat {
+            return;
+        }
+        let delay = self.elapsed.min(u8::MAX as _) as u8;
+        self.script.set_last_delay(delay);
+        if self.script.push(cmd) {
+            self.elapsed = 0;
+        }
+    }
+
+    pub fn finish_recording(&mut self) -> Macro {
+        self.state = MacroState::Idle;
+        self.store.set(self.slot, self.script);
+        self.script
+    }
+
+    pub fn rename(&mut self, slot: usize, name: &str) -> Macro {
+        let mut script = self.store.get(slot);
+        script.set_name(name.as_bytes());
+        self.store.set(slot, script);
+        if slot == self.slot {
+            self.script.set_name(name.as_bytes());
+        }
+        script
+    }
+
+    pub fn cancel(&mut self) {
+        if self.state == MacroState::Recording {
+            self.script = self.store.get(self.slot);
+        }
+        self.state = MacroState::Idle;
+    }
+
+    pub fn tick(&mut self) -> Option<NecCommand> {
+        match self.state {
+            MacroState::Idle => None,
+            MacroState::Recording => {
+                self.elapsed = self.elapsed.saturating_add(1);
+                None
+            }
+            MacroState::Playing => {
+                self.elapsed += 1;
+                if self.countdown > 0 {
+                    self.countdown -= 1;
+                    return None;
+                }
+                match self.script.steps().get(self.step) {
+                    Some(step) => {
+                        self.step += 1;
+                        self.countdown = step.delay;
+                        Some(step.cmd)
+                    }
+                    None => {
+                        self.state = MacroState::Idle;
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn progress(&self) -> u8 {
+        match self.state {
+            MacroState::Playing => {
+                let duration = self.script.duration().max(1);
+                (self.elapsed.min(duration) * 100 / duration) as _
+            }
+            _ => 0,
+        }
+    }
+}
+
+pub struct MacroTimer {
+    slot: usize,
+    minutes: u8,
+    countdown: Option<u32>,
+}
+
+impl MacroTimer {
+    pub const STEP: u8 = 5;
+    pub const MAX_MINUTES: u8 = 120;
+
+    pub fn new() -> Self {
+        Self {
+            slot: 0,
+            minutes: 30,
+            countdown: None,
+        }
+    }
+
+    pub fn slot(&self) -> usize {
+        self.slot
+    }
+
+    pub fn is_armed(&self) -> bool {
+        self.countdown.is_some()
+    }
+
+    pub fn increase(&mut self, step: u8) {
+        if !self.is_armed() {
+            self.minutes = self
+                .minutes
+                .saturating_add(Self::STEP.saturating_mul(step))
+                .clamp(Self::STEP, Self::MAX_MINUTES);
+        }
+    }
+
+    pub fn decrease(&mut self, step: u8) {
+        if !self.is_armed() {
+            self.minutes = self
+                .minutes
+                .saturating_sub(Self::STEP.saturating_mul(step))
+                .clamp(Self::STEP, Self::MAX_MINUTES);
+        }
+    }
+
+    pub fn arm(&mut self, slot: usize) {
+        self.slot = slot;
+        self.countdown = Some(self.duration());
+    }
+
+    pub fn disarm(&mut self) {
+        self.countdown = None;
+    }
+
+    pub fn remaining_secs(&self) -> u32 {
+        self.countdown
+            .unwrap_or_else(|| self.duration())
+            .div_ceil(10)
+    }
+
+    pub fn progress(&self) -> u8 {
+        match self.countdown {
+            Some(countdown) => {
+                let duration = self.duration();
+                ((duration - countdown.min(duration)) * 100 / duration) as _
+            }
+            None => 0,
+        }
+    }
+
+    pub fn tick(&mut self, ticks: u32) -> Option<usize> {
+        match self.countdown {
+            Some(countdown) if countdown <= ticks => {
+                self.countdown = None;
+                Some(self.slot)
+            }
+            Some(countdown) => {
+                self.countdown = Some(countdown - ticks);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn duration(&self) -> u32 {
+        self.minutes as u32 * 600
+    }
+}
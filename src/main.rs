@@ -6,27 +6,52 @@ extern crate panic_halt;
 extern crate rtic;
 
 mod app;
+mod battery;
 mod game;
+mod i2c_scan;
+mod input;
+mod macros;
+mod mono;
+mod power;
+mod relay;
+mod screens;
+mod sniffer;
+mod storage;
+mod text_entry;
+mod timers;
 mod ui;
+mod wakeup;
 
 use defmt_rtt as _;
 
 use app::*;
-use curio_bsp::hal::flash::WriteErase;
+use battery::Battery;
 use curio_bsp::hal::gpio::SignalEdge;
 use curio_bsp::hal::power::*;
 use curio_bsp::hal::rcc::*;
 use curio_bsp::hal::timer::Timer;
 use curio_bsp::stm32::*;
 use curio_bsp::*;
+use fugit::TimerDurationU64;
+use i2c_scan::I2cScan;
+use input::{Buttons, Input, InputConfig};
 use klaptik::{SpriteDisplay, Widget};
+use macros::Macro;
+use mono::TimerMono;
+use sniffer::Protocol;
+use storage::{Record, Store};
+use timers::{TimerId, Timers};
 use ui::*;
+use wakeup::Wakeup;
 
 #[rtic::app(device = stm32, peripherals = true, dispatchers = [CEC])]
 mod curio {
 
     use super::*;
 
+    #[monotonic(binds = TIM3, default = true)]
+    type Mono = TimerMono;
+
     #[shared]
     struct Shared {
         app: App,
@@ -35,23 +60,24 @@ mod curio {
         ir: IrTransceiver,
         exti: EXTI,
         i2c: I2cDev,
+        input: Input,
+        timers: Timers,
+        ui_timer: Timer<stm32::TIM14>,
+        wakeup: Wakeup,
     }
 
     #[local]
     struct Local {
         pwr: Power,
         scb: stm32::SCB,
-        flash: Option<FLASH>,
-        ui: Viewport,
-        ui_timer: Timer<stm32::TIM14>,
-        render_timer: Timer<stm32::TIM17>,
+        store: Store,
+        ui: Ui,
     }
 
     #[init]
     fn init(ctx: init::Context) -> (Shared, Local, init::Monotonics) {
         defmt::info!("init");
         let scb = ctx.core.SCB;
-        let flash = Some(ctx.device.FLASH);
         let mut exti = ctx.device.EXTI;
         let mut rcc = ctx.device.RCC.constrain();
         let mut pwr = ctx.device.PWR.constrain(&mut rcc);
@@ -61,7 +87,7 @@ mod curio {
         let Curio {
             mut control,
             mut display,
-            i2c,
+            mut i2c,
             ir,
         } = Curio::new(
             ctx.device.ADC,
@@ -78,17 +104,27 @@ mod curio {
         );
 
         let mut ui_timer = ctx.device.TIM14.timer(&mut rcc);
-        ui_timer.start(150.millis());
+        ui_timer.start(Input::IDLE_POLL_MS.millis());
         ui_timer.listen();
 
-        let mut render_timer = ctx.device.TIM17.timer(&mut rcc);
-        render_timer.start(100.millis());
-        render_timer.listen();
+        let mono = TimerMono::new(ctx.device.TIM3, &mut rcc);
+        let wakeup = Wakeup::new(ctx.device.RTC, &mut exti, &mut rcc);
+        battery_sample::spawn_after(TimerDurationU64::millis(Battery::SAMPLE_MS as _)).ok();
 
-        let options = Options::load();
+        let mut store = Store::detect(ctx.device.FLASH, &mut i2c);
+        let battery_voltage = control.battery_voltage();
+        let mut app = store.with(&mut i2c, |store| {
+            App::new(Options::load(store), battery_voltage, store)
+        });
+        restart_clock(&mut app);
+        let options = app.options;
         display.set_brightness(options.backlight);
-        let app = App::new(options, control.battery_voltage());
-        let ui = Viewport::new();
+        display.set_contrast(options.contrast);
+        display.set_inverted(options.invert);
+        display.set_flipped(options.flipped);
+        let ui = Ui::new();
+        let mut input = Input::new(InputConfig::DEFAULT);
+        input.set_flipped(options.flipped);
 
         let display = SpriteDisplay::new(display, SPRITES);
 
@@ -99,26 +135,29 @@ mod curio {
                 display,
                 exti,
                 i2c,
+                input,
                 ir,
+                timers: Timers::new(),
+                ui_timer,
+                wakeup,
             },
             Local {
-                flash,
-                ui_timer,
+                store,
                 ui,
-                render_timer,
                 pwr,
                 scb,
             },
-            init::Monotonics(),
+            init::Monotonics(mono),
         )
     }
 
-    #[task(binds = EXTI2_3, shared = [app, control, exti])]
+    #[task(binds = EXTI2_3, shared = [app, exti, input, ui_timer])]
     fn button_click(ctx: button_click::Context) {
         let button_click::SharedResources {
             mut app,
-            mut control,
             mut exti,
+            mut input,
+            mut ui_timer,
         } = ctx.shared;
 
         exti.lock(|exti| {
@@ -126,9 +165,12 @@ mod curio {
             exti.unpend(hal::exti::Event::GPIO3);
         });
 
-        if let Some(btn) = control.lock(|ctrl| ctrl.read_buttons()) {
-            app.lock(|app| app.handle_event(AppEvent::Button(btn)))
-                .map(app_request::spawn);
+        app.lock(resume);
+        if let Some(period) = input.lock(|input| input.wake()) {
+            ui_timer.lock(|timer| {
+                timer.start(period.millis());
+                timer.clear_irq();
+            });
         }
     }
 
@@ -140,24 +182,67 @@ mod curio {
             mut ir,
         } = ctx.shared;
         exti.lock(|exti| exti.unpend(hal::exti::Event::GPIO12));
+        // Only watch mode wakes on IR; a profile Stop waits for a button.
+        let asleep = app.lock(|app| {
+            if app.stopped && !app.watching {
+                return true;
+            }
+            resume(app);
+            false
+        });
+        if asleep {
+            return;
+        }
 
         if let Ok(Some(cmd)) = ir.lock(|ir| ir.event()) {
-            app.lock(|app| app.handle_event(AppEvent::IrCommand(cmd)))
+            let now_ms = monotonics::now().duration_since_epoch().to_millis() as u32;
+            app.lock(|app| app.handle_event(AppEvent::IrCommand(cmd, now_ms)))
                 .map(app_request::spawn);
+            render::spawn().ok();
         }
     }
 
-    #[task(binds = TIM14, local = [ui_timer], shared = [app, control])]
+    #[task(binds = TIM14, shared = [app, control, input, ui_timer])]
     fn ui_timer_tick(ctx: ui_timer_tick::Context) {
-        let mut app = ctx.shared.app;
-        let mut control = ctx.shared.control;
+        let ui_timer_tick::SharedResources {
+            mut app,
+            mut control,
+            mut input,
+            mut ui_timer,
+        } = ctx.shared;
 
-        if let Some(btn) = control.lock(|ctrl| ctrl.read_dpad()) {
-            app.lock(|app| app.handle_event(AppEvent::Button(btn)))
-                .map(app_request::spawn);
+        let pressed = control.lock(|ctrl| {
+            Buttons::default()
+                .with(ctrl.read_buttons())
+                .with(ctrl.read_dpad())
+        });
+        if let Some(gesture) = input.lock(|input| input.update(pressed)) {
+            app.lock(|app| {
+                app.handle_event(AppEvent::Gesture(gesture))
+                    .map(app_request::spawn);
+                restart_clock(app);
+            });
+            render::spawn().ok();
         }
 
-        ctx.local.ui_timer.clear_irq();
+        let period = input.lock(|input| input.reschedule());
+        ui_timer.lock(|timer| {
+            if let Some(period) = period {
+                timer.start(period.millis());
+            }
+            timer.clear_irq();
+        });
+    }
+
+    #[task(binds = RTC_TAMP, shared = [app, wakeup])]
+    fn stop_timeout(ctx: stop_timeout::Context) {
+        let stop_timeout::SharedResources {
+            mut app,
+            mut wakeup,
+        } = ctx.shared;
+        wakeup.lock(|wakeup| wakeup.cancel());
+        app.lock(|app| app.handle_event(AppEvent::StopTimeout))
+            .map(app_request::spawn);
     }
 
     #[task(binds = TIM16, shared = [ir])]
@@ -166,52 +251,210 @@ mod curio {
         ir.lock(|ir| ir.tick());
     }
 
-    #[task(binds = TIM17, local = [ui, render_timer], shared = [app, display])]
-    fn render_timer_tick(ctx: render_timer_tick::Context) {
+    fn resume(app: &mut App) {
+        app.resume().map(app_request::spawn);
+        restart_clock(app);
+    }
+
+    fn restart_clock(app: &mut App) {
+        if let Some((generation, period)) = app.restart_clock() {
+            if clock_tick::spawn(generation).is_ok() {
+                app.start_clock(generation, period);
+            }
+        }
+    }
+
+    #[task(shared = [app], capacity = 2)]
+    fn clock_tick(ctx: clock_tick::Context, generation: u8) {
+        let now_ms = monotonics::now().duration_since_epoch().to_millis() as u32;
+        let mut app = ctx.shared.app;
+        let (period, dirty) = app.lock(|app| {
+            if generation != app.clock_generation {
+                return (None, false);
+            }
+            app.handle_event(AppEvent::ClockTick(now_ms))
+                .map(app_request::spawn);
+            (app.schedule_clock(), app.dirty)
+        });
+        if let Some(period) = period {
+            clock_tick::spawn_after(TimerDurationU64::millis(period as _), generation).ok();
+        }
+        if dirty {
+            render::spawn().ok();
+        }
+    }
+
+    #[task(shared = [app, control])]
+    fn battery_sample(ctx: battery_sample::Context) {
+        battery_sample::spawn_after(TimerDurationU64::millis(Battery::SAMPLE_MS as _)).ok();
+
+        let mut app = ctx.shared.app;
+        let mut control = ctx.shared.control;
+        let sample = control.lock(|ctrl| ctrl.battery_voltage());
+        let dirty = app.lock(|app| {
+            app.handle_event(AppEvent::BatterySample(sample))
+                .map(app_request::spawn);
+            app.dirty
+        });
+        if dirty {
+            render::spawn().ok();
+        }
+    }
+
+    #[task(local = [ui], shared = [app, display])]
+    fn render(ctx: render::Context) {
         let ui = ctx.local.ui;
-        let render_timer = ctx.local.render_timer;
         let mut app = ctx.shared.app;
         let mut display = ctx.shared.display;
 
-        app.lock(|app| {
-            app.handle_event(AppEvent::ClockTick)
-                .map(app_request::spawn);
-            ui.update(app);
+        let redraw = app.lock(|app| {
+            let redraw = !app.standby && app.take_dirty();
+            if redraw {
+                if app.take_repaint() {
+                    ui.invalidate();
+                }
+                ui.update(app);
+            }
+            redraw
         });
-        display.lock(|display| ui.render(display));
+        if redraw {
+            display.lock(|display| ui.render(display));
+        }
+    }
+
+    #[task(capacity = 8, shared = [app, timers])]
+    fn timer_expired(ctx: timer_expired::Context, id: TimerId, generation: u8) {
+        let mut app = ctx.shared.app;
+        let mut timers = ctx.shared.timers;
+
+        if !timers.lock(|timers| timers.expire(id, generation)) {
+            return;
+        }
+        app.lock(|app| app.handle_event(AppEvent::TimerExpired(id)))
+            .map(app_request::spawn);
+        render::spawn().ok();
+    }
+
+    #[task(capacity = 4, shared = [app])]
+    fn ir_tx_done(ctx: ir_tx_done::Context) {
+        let mut app = ctx.shared.app;
+        app.lock(|app| app.handle_event(AppEvent::TransmitDone))
+            .map(app_request::spawn);
+    }
+
+    #[task(shared = [app, i2c])]
+    fn scan_i2c(ctx: scan_i2c::Context) {
+        let mut app = ctx.shared.app;
+        let mut i2c = ctx.shared.i2c;
 
-        render_timer.clear_irq();
+        let found = i2c.lock(|i2c| I2cScan::probe(|addr| i2c.read(addr, &mut [0]).is_ok()));
+        app.lock(|app| app.handle_event(AppEvent::I2cScanned(found)))
+            .map(app_request::spawn);
+        render::spawn().ok();
     }
 
-    #[task(capacity = 4, local = [flash, pwr, scb], shared = [i2c, ir, display])]
+    #[task(
+        capacity = 4,
+        local = [store, pwr, scb],
+        shared = [app, i2c, ir, display, input, timers, wakeup]
+    )]
     fn app_request(ctx: app_request::Context, req: AppRequest) {
         match req {
             AppRequest::SetBrightness(val) => {
                 let mut display = ctx.shared.display;
                 display.lock(|display| display.canvas().set_brightness(val));
             }
+            AppRequest::SetContrast(val) => {
+                let mut display = ctx.shared.display;
+                display.lock(|display| display.canvas().set_contrast(val));
+            }
+            AppRequest::SetInverted(inverted) => {
+                let mut display = ctx.shared.display;
+                display.lock(|display| display.canvas().set_inverted(inverted));
+            }
+            AppRequest::SetFlipped(flipped) => {
+                let mut display = ctx.shared.display;
+                display.lock(|display| display.canvas().set_flipped(flipped));
+                let mut input = ctx.shared.input;
+                input.lock(|input| input.set_flipped(flipped));
+            }
             AppRequest::TransmitIRCommand(cmd) => {
                 let mut ir = ctx.shared.ir;
                 ir.lock(|ir| ir.send(&cmd));
+                let frame = TimerDurationU64::millis(Protocol::Nec.frame_ms() as _);
+                ir_tx_done::spawn_after(frame).ok();
+            }
+            AppRequest::EnterStop(shutdown_after) => {
+                let mut display = ctx.shared.display;
+                display.lock(|display| display.canvas().set_brightness(0));
+                if let Some(secs) = shutdown_after {
+                    let mut wakeup = ctx.shared.wakeup;
+                    wakeup.lock(|wakeup| wakeup.start(secs));
+                }
+                let pwr = ctx.local.pwr;
+                pwr.set_mode(PowerMode::LowPower(LowPowerMode::StopMode1));
+                ctx.local.scb.set_sleepdeep();
+            }
+            AppRequest::Resume => {
+                let mut wakeup = ctx.shared.wakeup;
+                wakeup.lock(|wakeup| wakeup.cancel());
+                ctx.local.pwr.set_mode(PowerMode::Run);
+                ctx.local.scb.clear_sleepdeep();
             }
-            AppRequest::SwitchOff => {
+            AppRequest::SwitchOff(options) => {
+                let store = ctx.local.store;
+                let mut i2c = ctx.shared.i2c;
+                if let Some(options) = options {
+                    i2c.lock(|i2c| store.write(i2c, Record::Options, 0, &options.into_bytes()));
+                }
+                let mut app = ctx.shared.app;
+                let log =
+                    app.lock(|app| app.sniffer.take_unsaved().then(|| app.sniffer.to_bytes()));
+                if let Some(log) = log {
+                    i2c.lock(|i2c| store.write(i2c, Record::Log, 0, &log));
+                }
                 let pwr = ctx.local.pwr;
                 pwr.clear_wakeup_flag(WakeUp::Line4);
                 pwr.set_mode(PowerMode::LowPower(LowPowerMode::Shutdown));
                 ctx.local.scb.set_sleepdeep();
             }
             AppRequest::StoreOptions(options) => {
-                if let Some(flash) = ctx.local.flash.take() {
-                    hal::cortex_m::interrupt::free(|_| {
-                        if let Ok(mut unlocked) = flash.unlock() {
-                            unlocked.erase_page(Options::PAGE).ok();
-                            let addr = Options::PAGE.to_address();
-                            unlocked.write(addr, &options.into_bytes()).ok();
-                            *ctx.local.flash = Some(unlocked.lock());
-                        }
-                    });
+                let store = ctx.local.store;
+                let mut i2c = ctx.shared.i2c;
+                i2c.lock(|i2c| store.write(i2c, Record::Options, 0, &options.into_bytes()));
+            }
+            AppRequest::StoreMacro(slot, script) => {
+                let store = ctx.local.store;
+                let mut i2c = ctx.shared.i2c;
+                let offset = slot * Macro::SIZE;
+                i2c.lock(|i2c| store.write(i2c, Record::Macros, offset, &script.into_bytes()));
+            }
+            AppRequest::StoreRelayTable(table) => {
+                let store = ctx.local.store;
+                let mut i2c = ctx.shared.i2c;
+                i2c.lock(|i2c| store.write(i2c, Record::RelayTable, 0, &table.into_bytes()));
+            }
+            AppRequest::StoreLog => {
+                let store = ctx.local.store;
+                let mut app = ctx.shared.app;
+                let log = app.lock(|app| app.sniffer.to_bytes());
+                let mut i2c = ctx.shared.i2c;
+                i2c.lock(|i2c| store.write(i2c, Record::Log, 0, &log));
+            }
+            AppRequest::StartTimer(id, mode) => {
+                let mut timers = ctx.shared.timers;
+                if let Some(generation) = timers.lock(|timers| timers.start(id)) {
+                    let delay = TimerDurationU64::millis(mode.delay_ms() as _);
+                    timer_expired::spawn_after(delay, id, generation).ok();
                 }
             }
+            AppRequest::CancelTimer(id) => {
+                let mut timers = ctx.shared.timers;
+                timers.lock(|timers| timers.cancel(id));
+            }
+            AppRequest::ScanI2c => {
+                scan_i2c::spawn().ok();
+            }
         }
     }
 
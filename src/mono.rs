@@ -0,0 +1,70 @@
+use curio_bsp::hal::rcc::Rcc;
+use curio_bsp::stm32::TIM3;
+use fugit::{TimerDurationU64, TimerInstantU64};
+use rtic::Monotonic;
+
+// Millisecond monotonic on TIM3 that only interrupts on a compare match or
+// a counter wrap, so the core stays asleep between scheduled tasks.
+pub struct TimerMono {
+    tim: TIM3,
+    overflows: u64,
+}
+
+impl TimerMono {
+    pub const HZ: u32 = 1_000;
+
+    pub fn new(tim: TIM3, rcc: &mut Rcc) -> Self {
+        rcc.apbenr1.modify(|_, w| w.tim3en().set_bit());
+        let prescaler = rcc.clocks.apb_tim_clk.raw() / Self::HZ - 1;
+        tim.psc.write(|w| unsafe { w.bits(prescaler) });
+        tim.arr.write(|w| unsafe { w.bits(0xffff) });
+        tim.egr.write(|w| w.ug().set_bit());
+        tim.sr.write(|w| unsafe { w.bits(0) });
+        Self { tim, overflows: 0 }
+    }
+}
+
+impl Monotonic for TimerMono {
+    type Instant = TimerInstantU64<{ TimerMono::HZ }>;
+    type Duration = TimerDurationU64<{ TimerMono::HZ }>;
+
+    const DISABLE_INTERRUPT_ON_EMPTY_QUEUE: bool = false;
+
+    fn now(&mut self) -> Self::Instant {
+        let cnt = self.tim.cnt.read().bits() as u64 & 0xffff;
+        // A wrap that is pending but not yet handled belongs to this reading
+        // only if the counter has already restarted from zero.
+        let pending = self.tim.sr.read().uif().bit_is_set() && cnt < 0x8000;
+        let overflows = self.overflows + pending as u64;
+        Self::Instant::from_ticks((overflows << 16) | cnt)
+    }
+
+    fn set_compare(&mut self, instant: Self::Instant) {
+        // Deadlines further than one wrap away are re-armed by RTIC each time
+        // the compare fires early.
+        let ticks = instant.duration_since_epoch().ticks() as u32 & 0xffff;
+        self.tim.ccr1.write(|w| unsafe { w.bits(ticks) });
+    }
+
+    fn clear_compare_flag(&mut self) {
+        self.tim.sr.modify(|_, w| w.cc1if().clear_bit());
+    }
+
+    fn zero() -> Self::Instant {
+        Self::Instant::from_ticks(0)
+    }
+
+    unsafe fn reset(&mut self) {
+        self.tim
+            .dier
+            .modify(|_, w| w.cc1ie().set_bit().uie().set_bit());
+        self.tim.cr1.modify(|_, w| w.cen().set_bit());
+    }
+
+    fn on_interrupt(&mut self) {
+        if self.tim.sr.read().uif().bit_is_set() {
+            self.tim.sr.modify(|_, w| w.uif().clear_bit());
+            self.overflows += 1;
+        }
+    }
+}
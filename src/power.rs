@@ -0,0 +1,45 @@
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PowerProfile {
+    Performance = 0,
+    Balanced = 1,
+    Saver = 2,
+}
+
+impl PowerProfile {
+    pub fn from_byte(byte: u8) -> Self {
+        match byte {
+            0 => PowerProfile::Performance,
+            2 => PowerProfile::Saver,
+            _ => PowerProfile::Balanced,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            PowerProfile::Performance => PowerProfile::Balanced,
+            PowerProfile::Balanced => PowerProfile::Saver,
+            PowerProfile::Saver => PowerProfile::Performance,
+        }
+    }
+
+    pub fn prev(self) -> Self {
+        self.next().next()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PowerProfile::Performance => "Performance",
+            PowerProfile::Balanced => "Balanced",
+            PowerProfile::Saver => "Saver",
+        }
+    }
+
+    // Idle seconds before entering Stop mode.
+    pub fn stop_after(&self) -> Option<u32> {
+        match self {
+            PowerProfile::Performance => None,
+            PowerProfile::Balanced => Some(30),
+            PowerProfile::Saver => Some(10),
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use crate::sniffer::Protocol;
+use crate::storage::{Record, Storage};
+use curio_bsp::protocol::nec::NecCommand;
+
+#[derive(Clone, Copy)]
+pub struct RelayRule {
+    pub from: (u8, u8),
+    pub to: (u8, u8),
+}
+
+impl RelayRule {
+    pub const SIZE: usize = 4;
+}
+
+#[derive(Clone, Copy)]
+pub struct RelayTable {
+    rules: [RelayRule; Self::MAX_RULES],
+    len: usize,
+}
+
+impl RelayTable {
+    pub const MAX_RULES: usize = 8;
+    pub const HEADER_SIZE: usize = 8;
+    pub const SIZE: usize = Self::HEADER_SIZE + Self::MAX_RULES * RelayRule::SIZE;
+
+    pub fn new() -> Self {
+        let rule = RelayRule {
+            from: (0, 0),
+            to: (0, 0),
+        };
+        Self {
+            rules: [rule; Self::MAX_RULES],
+            len: 0,
+        }
+    }
+
+    pub fn load(store: &mut dyn Storage) -> Self {
+        let mut bytes = [0; Self::SIZE];
+        store.read(Record::RelayTable, 0, &mut bytes);
+        let mut table = Self::new();
+        let len = bytes[0] as usize;
+        if len > Self::MAX_RULES {
+            return table;
+        }
+        let chunks = bytes[Self::HEADER_SIZE..].chunks(RelayRule::SIZE);
+        for (rule, chunk) in table.rules.iter_mut().zip(chunks).take(len) {
+            rule.from = (chunk[0], chunk[1]);
+            rule.to = (chunk[2], chunk[3]);
+        }
+        table.len = len;
+        table
+    }
+
+    pub fn into_bytes(self) -> [u8; Self::SIZE] {
+        let mut bytes = [0xff; Self::SIZE];
+        bytes[0] = self.len as _;
+        let chunks = bytes[Self::HEADER_SIZE..].chunks_mut(RelayRule::SIZE);
+        for (chunk, rule) in chunks.zip(self.rules()) {
+            chunk.copy_from_slice(&[rule.from.0, rule.from.1, rule.to.0, rule.to.1]);
+        }
+        bytes
+    }
+
+    pub fn rules(&self) -> &[RelayRule] {
+        &self.rules[..self.len]
+    }
+
+    pub fn insert(&mut self, rule: RelayRule) {
+        if let Some(existing) = self.rules[..self.len]
+            .iter_mut()
+            .find(|existing| existing.from == rule.from)
+        {
+            existing.to = rule.to;
+        } else if self.len < Self::MAX_RULES {
+            self.rules[self.len] = rule;
+            self.len += 1;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn translate(&self, cmd: NecCommand) -> NecCommand {
+        let from = (cmd.addr, cmd.cmd);
+        match self.rules().iter().find(|rule| rule.from == from) {
+            Some(rule) => NecCommand {
+                addr: rule.to.0,
+                cmd: rule.to.1,
+                repeat: cmd.repeat,
+            },
+            None => cmd,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RelayMode {
+    Forward,
+    LearnSource,
+    LearnTarget(u8, u8),
+}
+
+pub struct Relay {
+    table: RelayTable,
+    mode: RelayMode,
+    relayed: u32,
+    echo: Option<(NecCommand, u32)>,
+}
+
+impl Relay {
+    // Our own frame is decoded by the receiver as it ends, so a copy of the
+    // last relayed frame is dropped until this long after it went out.
+    pub const ECHO_MARGIN_MS: u32 = 20;
+    pub const LEARN_TIMEOUT_MS: u32 = 10_000;
+
+    pub fn new(table: RelayTable) -> Self {
+        Self {
+            table,
+            mode: RelayMode::Forward,
+            relayed: 0,
+            echo: None,
+        }
+    }
+
+    pub fn table(&self) -> &RelayTable {
+        &self.table
+    }
+
+    pub fn mode(&self) -> RelayMode {
+        self.mode
+    }
+
+    pub fn relayed(&self) -> u32 {
+        self.relayed
+    }
+
+    pub fn is_learning(&self) -> bool {
+        self.mode != RelayMode::Forward
+    }
+
+    pub fn start_learning(&mut self) {
+        self.mode = RelayMode::LearnSource;
+    }
+
+    pub fn stop_learning(&mut self) {
+        self.mode = RelayMode::Forward;
+    }
+
+    pub fn clear_table(&mut self) {
+        self.table.clear();
+    }
+
+    pub fn learn(&mut self, cmd: NecCommand) -> bool {
+        match self.mode {
+            RelayMode::Forward => false,
+            RelayMode::LearnSource => {
+                self.mode = RelayMode::LearnTarget(cmd.addr, cmd.cmd);
+                false
+            }
+            RelayMode::LearnTarget(addr, command) => {
+                self.table.insert(RelayRule {
+                    from: (addr, command),
+                    to: (cmd.addr, cmd.cmd),
+                });
+                self.mode = RelayMode::Forward;
+                true
+            }
+        }
+    }
+
+    pub fn forward(&mut self, cmd: NecCommand, now_ms: u32) -> Option<NecCommand> {
+        if let Some((echo, sent_at)) = self.echo {
+            let window = Protocol::Nec.frame_ms() + Self::ECHO_MARGIN_MS;
+            let same = echo.addr == cmd.addr && echo.cmd == cmd.cmd && echo.repeat == cmd.repeat;
+            if same && now_ms.wrapping_sub(sent_at) <= window {
+                return None;
+            }
+        }
+        let out = self.table.translate(cmd);
+        self.echo = Some((out, now_ms));
+        self.relayed = self.relayed.wrapping_add(1);
+        Some(out)
+    }
+}
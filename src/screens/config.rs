@@ -0,0 +1,187 @@
+use super::*;
+use crate::app::Options;
+
+pub struct BacklightScreen;
+
+impl Screen for BacklightScreen {
+    fn handle_button(&self, app: &mut App, btn: Button, step: u8) -> Option<AppRequest> {
+        match btn {
+            Button::A => {
+                app.back();
+                Some(AppRequest::StoreOptions(app.options))
+            }
+            Button::Up => {
+                app.options.backlight = app.options.backlight.saturating_add(step).clamp(0, 10);
+                Some(AppRequest::SetBrightness(app.options.backlight))
+            }
+            Button::Down => {
+                app.options.backlight = app.options.backlight.saturating_sub(step);
+                Some(AppRequest::SetBrightness(app.options.backlight))
+            }
+            Button::Left => {
+                let delta = Options::DIM_STEP.saturating_mul(step);
+                app.options.dim_timeout = app.options.dim_timeout.saturating_sub(delta);
+                None
+            }
+            Button::Right => {
+                let delta = Options::DIM_STEP.saturating_mul(step);
+                app.options.dim_timeout = app
+                    .options
+                    .dim_timeout
+                    .saturating_add(delta)
+                    .min(Options::MAX_DIM_TIMEOUT);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn accepts_repeat(&self) -> bool {
+        true
+    }
+}
+
+pub struct ContrastScreen;
+
+impl Screen for ContrastScreen {
+    fn handle_button(&self, app: &mut App, btn: Button, step: u8) -> Option<AppRequest> {
+        match btn {
+            Button::A => {
+                app.back();
+                Some(AppRequest::StoreOptions(app.options))
+            }
+            Button::Up => {
+                app.options.contrast = app
+                    .options
+                    .contrast
+                    .saturating_add(step)
+                    .min(Options::MAX_CONTRAST);
+                Some(AppRequest::SetContrast(app.options.contrast))
+            }
+            Button::Down => {
+                app.options.contrast = app
+                    .options
+                    .contrast
+                    .saturating_sub(step)
+                    .max(Options::MIN_CONTRAST);
+                Some(AppRequest::SetContrast(app.options.contrast))
+            }
+            _ => None,
+        }
+    }
+
+    fn accepts_repeat(&self) -> bool {
+        true
+    }
+}
+
+pub struct SleepTimeoutScreen;
+
+impl Screen for SleepTimeoutScreen {
+    fn handle_button(&self, app: &mut App, btn: Button, step: u8) -> Option<AppRequest> {
+        let delta = step.saturating_mul(5);
+        match btn {
+            Button::A => {
+                app.back();
+                return Some(AppRequest::StoreOptions(app.options));
+            }
+            Button::Up => {
+                app.options.sleep_timeout = app
+                    .options
+                    .sleep_timeout
+                    .saturating_add(delta)
+                    .clamp(10, 90)
+            }
+            Button::Left => app.options.power = app.options.power.prev(),
+            Button::Right => app.options.power = app.options.power.next(),
+            Button::Down => {
+                app.options.sleep_timeout = app
+                    .options
+                    .sleep_timeout
+                    .saturating_sub(delta)
+                    .clamp(10, 90)
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn accepts_repeat(&self) -> bool {
+        true
+    }
+}
+
+pub struct AboutScreen;
+
+impl Screen for AboutScreen {
+    fn handle_button(&self, app: &mut App, btn: Button, _: u8) -> Option<AppRequest> {
+        match btn {
+            Button::A => app.open(ViewportNode::Game),
+            Button::Down => app.open(ViewportNode::Battery),
+            _ => {}
+        }
+        None
+    }
+}
+
+pub struct BatteryScreen;
+
+impl Screen for BatteryScreen {
+    fn handle_button(&self, app: &mut App, btn: Button, _: u8) -> Option<AppRequest> {
+        match btn {
+            Button::Up | Button::Down => {
+                app.options.chemistry = app.options.chemistry.toggle();
+                app.options_dirty = true;
+                app.battery.set_chemistry(app.options.chemistry);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_back(&self, app: &mut App) -> Option<AppRequest> {
+        app.back();
+        app.save_options()
+    }
+
+    fn handle_long_press(&self, app: &mut App, btn: Button) -> Option<AppRequest> {
+        let Button::B = btn else {
+            return None;
+        };
+        app.home()
+    }
+}
+
+pub struct BatteryWarningScreen;
+
+impl Screen for BatteryWarningScreen {
+    fn handle_button(&self, app: &mut App, _: Button, _: u8) -> Option<AppRequest> {
+        app.back();
+        None
+    }
+}
+
+pub struct GameScreen;
+
+impl Screen for GameScreen {
+    fn handle_button(&self, app: &mut App, btn: Button, _: u8) -> Option<AppRequest> {
+        app.game.button_click(btn);
+        None
+    }
+
+    fn handle_back(&self, app: &mut App) -> Option<AppRequest> {
+        app.game.button_click(Button::B);
+        None
+    }
+
+    fn handle_long_press(&self, app: &mut App, btn: Button) -> Option<AppRequest> {
+        if let Button::B = btn {
+            app.back();
+        }
+        None
+    }
+
+    fn accepts_repeat(&self) -> bool {
+        true
+    }
+}
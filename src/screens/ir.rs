@@ -0,0 +1,163 @@
+use super::*;
+use crate::app::Radix;
+use crate::relay::Relay;
+use crate::timers::{TimerId, TimerMode};
+
+pub struct ScanScreen;
+
+impl Screen for ScanScreen {
+    fn handle_button(&self, app: &mut App, btn: Button, _: u8) -> Option<AppRequest> {
+        match btn {
+            Button::A => return Some(AppRequest::TransmitIRCommand(app.rx_cmd)),
+            Button::Up | Button::Down => {
+                app.options.radix = app.options.radix.toggle();
+                app.options_dirty = true;
+                app.send_cursor = app.send_cursor.min(send_fields(app.options.radix) - 1);
+            }
+            Button::Left => app.open(ViewportNode::Log),
+            Button::Right => app.open(ViewportNode::Relay),
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_back(&self, app: &mut App) -> Option<AppRequest> {
+        app.back();
+        app.save_options()
+    }
+
+    fn handle_long_press(&self, app: &mut App, btn: Button) -> Option<AppRequest> {
+        let Button::B = btn else {
+            return None;
+        };
+        app.home()
+    }
+}
+
+pub struct LogScreen;
+
+impl Screen for LogScreen {
+    fn handle_button(&self, app: &mut App, btn: Button, _: u8) -> Option<AppRequest> {
+        match btn {
+            Button::A => app.sniffer.toggle_freeze(),
+            Button::Up => app.sniffer.scroll_up(),
+            Button::Down => app.sniffer.scroll_down(),
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_back(&self, app: &mut App) -> Option<AppRequest> {
+        app.back();
+        app.save_log()
+    }
+
+    fn handle_long_press(&self, app: &mut App, btn: Button) -> Option<AppRequest> {
+        match btn {
+            Button::Up => app.sniffer.scroll_to_newest(),
+            Button::B => return app.home().or_else(|| app.save_log()),
+            _ => {}
+        }
+        None
+    }
+
+    fn accepts_repeat(&self) -> bool {
+        true
+    }
+}
+
+pub struct RelayScreen;
+
+impl Screen for RelayScreen {
+    fn handle_button(&self, app: &mut App, btn: Button, _: u8) -> Option<AppRequest> {
+        match btn {
+            Button::A => {
+                app.relay.start_learning();
+                let timeout = TimerMode::Once(Relay::LEARN_TIMEOUT_MS);
+                return Some(AppRequest::StartTimer(TimerId::RelayLearn, timeout));
+            }
+            Button::Left => {
+                app.options.ir_wake = !app.options.ir_wake;
+                return Some(AppRequest::StoreOptions(app.options));
+            }
+            Button::Right if !app.relay.is_learning() => {
+                app.relay.clear_table();
+                return Some(AppRequest::StoreRelayTable(*app.relay.table()));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_back(&self, app: &mut App) -> Option<AppRequest> {
+        if app.relay.is_learning() {
+            app.relay.stop_learning();
+            return Some(AppRequest::CancelTimer(TimerId::RelayLearn));
+        }
+        app.back();
+        None
+    }
+}
+
+pub struct SendScreen;
+
+impl Screen for SendScreen {
+    fn handle_button(&self, app: &mut App, btn: Button, step: u8) -> Option<AppRequest> {
+        let fields = send_fields(app.options.radix);
+        match btn {
+            Button::A => return Some(AppRequest::TransmitIRCommand(app.tx_cmd)),
+            Button::Left => app.send_cursor = app.send_cursor.saturating_sub(1),
+            Button::Right => app.send_cursor = (app.send_cursor + 1).min(fields - 1),
+            Button::Up => adjust_tx_cmd(app, step as i8),
+            Button::Down => adjust_tx_cmd(app, -(step as i8)),
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_long_press(&self, app: &mut App, btn: Button) -> Option<AppRequest> {
+        match btn {
+            Button::Left | Button::Right => {
+                app.tx_cmd.addr = 0;
+                app.tx_cmd.cmd = 0;
+            }
+            Button::B => return app.home(),
+            _ => {}
+        }
+        None
+    }
+
+    fn accepts_repeat(&self) -> bool {
+        true
+    }
+}
+
+fn send_fields(radix: Radix) -> u8 {
+    match radix {
+        Radix::Dec => 2,
+        Radix::Hex => 4,
+    }
+}
+
+fn adjust_tx_cmd(app: &mut App, delta: i8) {
+    let (field, shift) = match app.options.radix {
+        Radix::Dec => (app.send_cursor, None),
+        Radix::Hex => (app.send_cursor / 2, Some(4 - app.send_cursor % 2 * 4)),
+    };
+    let delta = match shift {
+        Some(_) => delta.signum(),
+        None => delta,
+    };
+    let value = if field == 0 {
+        &mut app.tx_cmd.addr
+    } else {
+        &mut app.tx_cmd.cmd
+    };
+    match shift {
+        None => *value = value.wrapping_add(delta as u8),
+        Some(shift) => {
+            let nibble = (*value >> shift).wrapping_add(delta as u8) & 0x0f;
+            *value = *value & !(0x0f << shift) | nibble << shift;
+        }
+    }
+}
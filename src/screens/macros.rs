@@ -0,0 +1,56 @@
+use super::*;
+use crate::macros::MacroState;
+use crate::text_entry::{NameTarget, TextEntry};
+
+pub struct ReplayScreen;
+
+impl Screen for ReplayScreen {
+    fn handle_button(&self, app: &mut App, btn: Button, _: u8) -> Option<AppRequest> {
+        match btn {
+            Button::A => match app.macros.state() {
+                MacroState::Idle => app.macros.play(),
+                MacroState::Recording => {
+                    let slot = app.macros.slot();
+                    let script = app.macros.finish_recording();
+                    app.text_entry = TextEntry::new(NameTarget::Macro(slot), script.name());
+                    app.open(ViewportNode::NameEntry);
+                    return Some(AppRequest::StoreMacro(slot, script));
+                }
+                MacroState::Playing => {}
+            },
+            Button::Left if app.macros.state() == MacroState::Idle => app.open(ViewportNode::Timer),
+            Button::Right => app.macros.record(),
+            Button::Up => app.macros.select_prev(),
+            Button::Down => app.macros.select_next(),
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_back(&self, app: &mut App) -> Option<AppRequest> {
+        match app.macros.state() {
+            MacroState::Idle => app.back(),
+            _ => app.macros.cancel(),
+        }
+        None
+    }
+}
+
+pub struct TimerScreen;
+
+impl Screen for TimerScreen {
+    fn handle_button(&self, app: &mut App, btn: Button, step: u8) -> Option<AppRequest> {
+        match btn {
+            Button::A if app.timer.is_armed() => app.timer.disarm(),
+            Button::A => app.timer.arm(app.macros.slot()),
+            Button::Up => app.timer.increase(step),
+            Button::Down => app.timer.decrease(step),
+            _ => {}
+        }
+        None
+    }
+
+    fn accepts_repeat(&self) -> bool {
+        true
+    }
+}
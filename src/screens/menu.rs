@@ -0,0 +1,72 @@
+use super::*;
+use crate::ui::{MenuAction, MenuEntry, MenuItem};
+
+pub const MAIN_MENU: &[MenuEntry] = &[
+    MenuEntry::new(MenuItem::Scan, MenuAction::Open(ViewportNode::Scan)),
+    MenuEntry::new(MenuItem::Send, MenuAction::Open(ViewportNode::Send)),
+    MenuEntry::new(MenuItem::Replay, MenuAction::Open(ViewportNode::Replay)),
+    MenuEntry::new(MenuItem::Tools, MenuAction::Run(start_i2c_scan)),
+    MenuEntry::new(MenuItem::Config, MenuAction::Submenu(CONFIG_MENU)),
+    MenuEntry::new(MenuItem::Curio, MenuAction::Open(ViewportNode::Game)),
+];
+
+pub const CONFIG_MENU: &[MenuEntry] = &[
+    MenuEntry::new(MenuItem::About, MenuAction::Open(ViewportNode::About)),
+    MenuEntry::new(
+        MenuItem::Sleep,
+        MenuAction::Open(ViewportNode::SleepTimeout),
+    ),
+    MenuEntry::new(
+        MenuItem::Backlight,
+        MenuAction::Open(ViewportNode::Backlight),
+    ),
+    MenuEntry::new(MenuItem::Contrast, MenuAction::Open(ViewportNode::Contrast)),
+    MenuEntry::new(
+        MenuItem::Invert,
+        MenuAction::Toggle(|options| {
+            options.invert = !options.invert;
+            AppRequest::SetInverted(options.invert)
+        }),
+    ),
+    MenuEntry::new(
+        MenuItem::Rotate,
+        MenuAction::Toggle(|options| {
+            options.flipped = !options.flipped;
+            AppRequest::SetFlipped(options.flipped)
+        }),
+    ),
+];
+
+pub struct MenuScreen;
+
+impl Screen for MenuScreen {
+    fn handle_button(&self, app: &mut App, btn: Button, _: u8) -> Option<AppRequest> {
+        match btn {
+            Button::A => match app.menu.selected().action {
+                MenuAction::Open(node) => app.open(node),
+                MenuAction::Submenu(entries) => app.menu.enter(entries),
+                MenuAction::Toggle(toggle) => {
+                    app.options_dirty = true;
+                    app.repaint();
+                    return Some(toggle(&mut app.options));
+                }
+                MenuAction::Run(command) => return command(app),
+            },
+            Button::Up => app.menu.move_up(),
+            Button::Down => app.menu.move_down(),
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_back(&self, app: &mut App) -> Option<AppRequest> {
+        if app.menu.leave() {
+            return app.save_options();
+        }
+        app.switch_off()
+    }
+
+    fn accepts_repeat(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,61 @@
+use crate::app::{App, AppRequest};
+use crate::ui::ViewportNode;
+use curio_bsp::Button;
+
+mod config;
+mod ir;
+mod macros;
+mod menu;
+mod nav;
+mod text;
+mod tools;
+
+pub use config::*;
+pub use ir::*;
+pub use macros::*;
+pub use menu::*;
+pub use nav::*;
+pub use text::*;
+pub use tools::*;
+
+pub trait Screen {
+    fn handle_button(&self, app: &mut App, btn: Button, step: u8) -> Option<AppRequest>;
+
+    fn handle_back(&self, app: &mut App) -> Option<AppRequest> {
+        app.back();
+        None
+    }
+
+    fn handle_long_press(&self, app: &mut App, btn: Button) -> Option<AppRequest> {
+        if let Button::B = btn {
+            return app.home();
+        }
+        None
+    }
+
+    fn accepts_repeat(&self) -> bool {
+        false
+    }
+}
+
+pub fn screen(node: ViewportNode) -> &'static dyn Screen {
+    match node {
+        ViewportNode::Menu => &MenuScreen,
+        ViewportNode::Scan => &ScanScreen,
+        ViewportNode::Relay => &RelayScreen,
+        ViewportNode::Log => &LogScreen,
+        ViewportNode::Send => &SendScreen,
+        ViewportNode::Replay => &ReplayScreen,
+        ViewportNode::Timer => &TimerScreen,
+        ViewportNode::Backlight => &BacklightScreen,
+        ViewportNode::Contrast => &ContrastScreen,
+        ViewportNode::SleepTimeout => &SleepTimeoutScreen,
+        ViewportNode::About => &AboutScreen,
+        ViewportNode::Game => &GameScreen,
+        ViewportNode::NameEntry => &NameEntryScreen,
+        ViewportNode::NameConfirm => &NameConfirmScreen,
+        ViewportNode::Battery => &BatteryScreen,
+        ViewportNode::BatteryWarning => &BatteryWarningScreen,
+        ViewportNode::I2cScan => &I2cScanScreen,
+    }
+}
@@ -0,0 +1,42 @@
+use crate::ui::ViewportNode;
+
+pub struct NavStack {
+    stack: [ViewportNode; Self::DEPTH],
+    depth: usize,
+}
+
+impl NavStack {
+    pub const DEPTH: usize = 8;
+
+    pub fn new(root: ViewportNode) -> Self {
+        Self {
+            stack: [root; Self::DEPTH],
+            depth: 1,
+        }
+    }
+
+    pub fn current(&self) -> ViewportNode {
+        self.stack[self.depth - 1]
+    }
+
+    pub fn push(&mut self, node: ViewportNode) {
+        if self.depth == Self::DEPTH {
+            self.stack.copy_within(2.., 1);
+            self.depth -= 1;
+        }
+        self.stack[self.depth] = node;
+        self.depth += 1;
+    }
+
+    pub fn pop(&mut self) -> bool {
+        if self.depth == 1 {
+            return false;
+        }
+        self.depth -= 1;
+        true
+    }
+
+    pub fn reset(&mut self) {
+        self.depth = 1;
+    }
+}
@@ -0,0 +1,47 @@
+use super::*;
+use crate::text_entry::NameTarget;
+
+pub struct NameEntryScreen;
+
+impl Screen for NameEntryScreen {
+    fn handle_button(&self, app: &mut App, btn: Button, step: u8) -> Option<AppRequest> {
+        match btn {
+            Button::A => app.open(ViewportNode::NameConfirm),
+            Button::Up => app.text_entry.next_char(step),
+            Button::Down => app.text_entry.prev_char(step),
+            Button::Left => app.text_entry.move_left(),
+            Button::Right => app.text_entry.move_right(),
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_back(&self, app: &mut App) -> Option<AppRequest> {
+        if !app.text_entry.delete() {
+            app.back();
+        }
+        None
+    }
+
+    fn accepts_repeat(&self) -> bool {
+        true
+    }
+}
+
+pub struct NameConfirmScreen;
+
+impl Screen for NameConfirmScreen {
+    fn handle_button(&self, app: &mut App, btn: Button, _: u8) -> Option<AppRequest> {
+        let Button::A = btn else {
+            return None;
+        };
+        app.back();
+        app.back();
+        match app.text_entry.target() {
+            NameTarget::Macro(slot) => {
+                let script = app.macros.rename(slot, app.text_entry.text());
+                Some(AppRequest::StoreMacro(slot, script))
+            }
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use super::*;
+use crate::ui::ListSource;
+
+pub fn start_i2c_scan(app: &mut App) -> Option<AppRequest> {
+    if app.active_widget() != ViewportNode::I2cScan {
+        app.open(ViewportNode::I2cScan);
+    }
+    if !app.i2c_scan.start() {
+        return None;
+    }
+    app.i2c_view.reset();
+    Some(AppRequest::ScanI2c)
+}
+
+pub struct I2cScanScreen;
+
+impl Screen for I2cScanScreen {
+    fn handle_button(&self, app: &mut App, btn: Button, _: u8) -> Option<AppRequest> {
+        match btn {
+            Button::A => return start_i2c_scan(app),
+            Button::Up => app.i2c_view.move_up(),
+            Button::Down => app.i2c_view.move_down(app.i2c_scan.len()),
+            _ => {}
+        }
+        None
+    }
+
+    fn accepts_repeat(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,204 @@
+use crate::storage::{Record, Storage};
+use curio_bsp::protocol::nec::NecCommand;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Nec,
+}
+
+impl Protocol {
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Protocol::Nec),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Protocol::Nec => "NEC",
+        }
+    }
+
+    // Time a frame spends on air; NEC frames are fixed length.
+    pub fn frame_ms(&self) -> u32 {
+        match self {
+            Protocol::Nec => 68,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct LogEntry {
+    pub timestamp: u32,
+    pub protocol: Protocol,
+    pub addr: u8,
+    pub cmd: u8,
+    pub repeats: u8,
+}
+
+impl LogEntry {
+    pub const SIZE: usize = 8;
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Some(Self {
+            timestamp: u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            protocol: Protocol::from_byte(bytes[4])?,
+            addr: bytes[5],
+            cmd: bytes[6],
+            repeats: bytes[7],
+        })
+    }
+
+    fn into_bytes(self) -> [u8; Self::SIZE] {
+        let [t0, t1, t2, t3] = self.timestamp.to_le_bytes();
+        let protocol = self.protocol as u8;
+        [t0, t1, t2, t3, protocol, self.addr, self.cmd, self.repeats]
+    }
+}
+
+pub struct Sniffer {
+    entries: [LogEntry; Self::CAPACITY],
+    head: usize,
+    len: usize,
+    scroll: usize,
+    frozen: bool,
+    unsaved: bool,
+}
+
+impl Sniffer {
+    pub const CAPACITY: usize = 32;
+    pub const HEADER_SIZE: usize = 2;
+    pub const SIZE: usize = Self::HEADER_SIZE + Self::CAPACITY * LogEntry::SIZE;
+
+    pub fn new() -> Self {
+        let entry = LogEntry {
+            timestamp: 0,
+            protocol: Protocol::Nec,
+            addr: 0,
+            cmd: 0,
+            repeats: 0,
+        };
+        Self {
+            entries: [entry; Self::CAPACITY],
+            head: 0,
+            len: 0,
+            scroll: 0,
+            frozen: false,
+            unsaved: false,
+        }
+    }
+
+    pub fn load(store: &mut dyn Storage) -> Self {
+        let mut bytes = [0; Self::SIZE];
+        store.read(Record::Log, 0, &mut bytes);
+        let mut sniffer = Self::new();
+        let (head, len) = (bytes[0] as usize, bytes[1] as usize);
+        if head >= Self::CAPACITY || len > Self::CAPACITY {
+            return sniffer;
+        }
+        let chunks = bytes[Self::HEADER_SIZE..].chunks(LogEntry::SIZE);
+        for (entry, chunk) in sniffer.entries.iter_mut().zip(chunks) {
+            match LogEntry::from_bytes(chunk) {
+                Some(loaded) => *entry = loaded,
+                None => return Self::new(),
+            }
+        }
+        sniffer.head = head;
+        sniffer.len = len;
+        sniffer
+    }
+
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        bytes[0] = self.head as _;
+        bytes[1] = self.len as _;
+        let chunks = bytes[Self::HEADER_SIZE..].chunks_mut(LogEntry::SIZE);
+        for (chunk, entry) in chunks.zip(self.entries) {
+            chunk.copy_from_slice(&entry.into_bytes());
+        }
+        bytes
+    }
+
+    // Set by every change since the log was last written out.
+    pub fn take_unsaved(&mut self) -> bool {
+        core::mem::take(&mut self.unsaved)
+    }
+
+    pub fn record(&mut self, timestamp: u32, cmd: NecCommand) {
+        if self.frozen {
+            return;
+        }
+
+        if cmd.repeat {
+            if let Some(last) = self.newest_mut() {
+                if last.addr == cmd.addr && last.cmd == cmd.cmd {
+                    last.repeats = last.repeats.saturating_add(1);
+                    self.unsaved = true;
+                    return;
+                }
+            }
+        }
+
+        self.entries[self.head] = LogEntry {
+            timestamp,
+            protocol: Protocol::Nec,
+            addr: cmd.addr,
+            cmd: cmd.cmd,
+            repeats: 0,
+        };
+        self.head = (self.head + 1) % Self::CAPACITY;
+        self.len = (self.len + 1).min(Self::CAPACITY);
+        self.unsaved = true;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    pub fn toggle_freeze(&mut self) {
+        self.frozen = !self.frozen;
+        if !self.frozen {
+            self.scroll = 0;
+        }
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+
+    pub fn scroll_to_newest(&mut self) {
+        self.scroll = 0;
+    }
+
+    pub fn scroll_down(&mut self) {
+        self.frozen = true;
+        if self.scroll + 1 < self.len {
+            self.scroll += 1;
+        }
+    }
+
+    pub fn entry(&self, idx: usize) -> Option<&LogEntry> {
+        if idx >= self.len {
+            return None;
+        }
+        let offset = (self.head + Self::CAPACITY - 1 - idx) % Self::CAPACITY;
+        Some(&self.entries[offset])
+    }
+
+    fn newest_mut(&mut self) -> Option<&mut LogEntry> {
+        if self.len == 0 {
+            return None;
+        }
+        let offset = (self.head + Self::CAPACITY - 1) % Self::CAPACITY;
+        Some(&mut self.entries[offset])
+    }
+}
@@ -0,0 +1,351 @@
+use crate::app::Options;
+use crate::macros::MacroStore;
+use crate::relay::RelayTable;
+use crate::sniffer::Sniffer;
+use curio_bsp::hal::flash::{FlashPage, WriteErase};
+use curio_bsp::hal::prelude::*;
+use curio_bsp::stm32::FLASH;
+use curio_bsp::I2cDev;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Record {
+    Options = 0,
+    Macros = 1,
+    RelayTable = 2,
+    Log = 3,
+}
+
+impl Record {
+    // Largest fixed record, also the scratch buffer for copying one.
+    pub const CAPACITY: usize = 256;
+    const ALL: [Record; 4] = [
+        Record::Options,
+        Record::Macros,
+        Record::RelayTable,
+        Record::Log,
+    ];
+    const FIXED: [Record; 3] = [Record::Options, Record::Macros, Record::RelayTable];
+
+    pub fn size(self) -> usize {
+        match self {
+            Record::Options => Options::SIZE,
+            Record::Macros => MacroStore::SIZE,
+            Record::RelayTable => RelayTable::SIZE,
+            Record::Log => Sniffer::SIZE,
+        }
+    }
+
+    // Internal flash only has room for the settings, one page each; the log
+    // is only kept on an EEPROM. The
+    // pages are kept clear of the firmware by the assertion in storage.x.
+    fn page(self) -> Option<FlashPage> {
+        match self {
+            Record::Options | Record::Macros | Record::RelayTable => {
+                Some(FlashPage(31 - self as usize))
+            }
+            Record::Log => None,
+        }
+    }
+}
+
+pub trait Storage {
+    fn read(&mut self, record: Record, offset: usize, buf: &mut [u8]);
+
+    fn write(&mut self, record: Record, offset: usize, data: &[u8]);
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Backend {
+    Flash,
+    Eeprom(Part),
+}
+
+// Picks the external EEPROM when a usable one answers at boot, internal
+// flash otherwise. A blank EEPROM is seeded from flash on first use.
+pub struct Store {
+    backend: Backend,
+    flash: Option<FLASH>,
+}
+
+impl Store {
+    pub fn detect(flash: FLASH, i2c: &mut I2cDev) -> Self {
+        let mut flash = Some(flash);
+        let backend = match Eeprom::open(i2c, &mut Flash(&mut flash)) {
+            Some(part) => Backend::Eeprom(part),
+            None => Backend::Flash,
+        };
+        Self { backend, flash }
+    }
+
+    pub fn with<R>(&mut self, i2c: &mut I2cDev, f: impl FnOnce(&mut dyn Storage) -> R) -> R {
+        match self.backend {
+            Backend::Flash => f(&mut Flash(&mut self.flash)),
+            Backend::Eeprom(part) => f(&mut Eeprom { i2c, part }),
+        }
+    }
+
+    pub fn write(&mut self, i2c: &mut I2cDev, record: Record, offset: usize, data: &[u8]) {
+        self.with(i2c, |store| store.write(record, offset, data));
+    }
+}
+
+// Records live in the last pages of the internal flash, one page each.
+pub struct Flash<'a>(pub &'a mut Option<FLASH>);
+
+impl Storage for Flash<'_> {
+    fn read(&mut self, record: Record, offset: usize, buf: &mut [u8]) {
+        let Some(page) = record.page() else {
+            buf.fill(0xff);
+            return;
+        };
+        let addr = page.to_address() + offset;
+        unsafe { core::ptr::copy_nonoverlapping(addr as *const u8, buf.as_mut_ptr(), buf.len()) };
+    }
+
+    fn write(&mut self, record: Record, offset: usize, data: &[u8]) {
+        let Some(page) = record.page() else {
+            return;
+        };
+        let mut buf = [0xff; Record::CAPACITY];
+        let buf = &mut buf[..record.size()];
+        self.read(record, 0, buf);
+        buf[offset..][..data.len()].copy_from_slice(data);
+
+        let flash = &mut *self.0;
+        if let Some(locked) = flash.take() {
+            curio_bsp::hal::cortex_m::interrupt::free(|_| {
+                if let Ok(mut unlocked) = locked.unlock() {
+                    unlocked.erase_page(page).ok();
+                    unlocked.write(page.to_address(), buf).ok();
+                    *flash = Some(unlocked.lock());
+                }
+            });
+        }
+    }
+}
+
+// An AT24Cxx part strapped to 0x50. Up to 2 KiB (AT24C16) the high address
+// bits go in the bus address and one address byte follows; larger parts
+// (AT24C32 and up) take two address bytes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Part {
+    pub size: usize,
+    pub wide: bool,
+}
+
+impl Part {
+    pub const ADDRESS: u8 = 0x50;
+    // Smaller parts leave no room for the log.
+    pub const MIN_SIZE: usize = 1024;
+    const NARROW_PAGE: usize = 16;
+    const WIDE_PAGE: usize = 32;
+    const WIDE_SIZES: [usize; 4] = [4096, 8192, 16384, 32768];
+    const MAX_SIZE: usize = 65536;
+
+    // Narrow parts answer on one bus address per 256 bytes.
+    fn detect(i2c: &mut I2cDev) -> Option<Self> {
+        let footprint = (0..8)
+            .take_while(|block| i2c.read(Self::ADDRESS + block, &mut [0]).is_ok())
+            .count();
+        if footprint == 0 {
+            return None;
+        }
+        let narrow = Self::is_narrow(i2c)?;
+        // A part that never goes busy may be a narrow FRAM, which looks wide
+        // to the probe; with more than one bus address it is left alone.
+        if !narrow && footprint > 1 {
+            return None;
+        }
+        let part = if narrow {
+            Part {
+                size: (1 << footprint.ilog2()) * 256,
+                wide: false,
+            }
+        } else {
+            Part {
+                size: Self::MAX_SIZE,
+                wide: true,
+            }
+        };
+        (part.size >= Self::MIN_SIZE).then_some(part)
+    }
+
+    // Two bytes with a stop are only an address to a wide part, but a narrow
+    // one takes the second byte as data and stops answering while it writes
+    // it. The byte written is the one already stored at address zero, so
+    // neither kind ends up with different contents.
+    fn is_narrow(i2c: &mut I2cDev) -> Option<bool> {
+        let mut original = [0];
+        i2c.write_read(Self::ADDRESS, &[0], &mut original).ok()?;
+        i2c.write(Self::ADDRESS, &[0, original[0]]).ok()?;
+        let busy = i2c.read(Self::ADDRESS, &mut [0]).is_err();
+        Eeprom::wait_ready(i2c);
+        Some(busy)
+    }
+
+    fn page_size(&self) -> usize {
+        if self.wide {
+            Self::WIDE_PAGE
+        } else {
+            Self::NARROW_PAGE
+        }
+    }
+
+    // A header page, then each record from a fresh page in `Record::ALL`
+    // order.
+    fn offset(&self, record: Record) -> usize {
+        let align = |len: usize| len.next_multiple_of(self.page_size());
+        Record::ALL
+            .iter()
+            .take_while(|rec| **rec != record)
+            .fold(align(Eeprom::HEADER_SIZE), |offset, rec| {
+                offset + align(rec.size())
+            })
+    }
+}
+
+pub struct Eeprom<'a> {
+    i2c: &'a mut I2cDev,
+    part: Part,
+}
+
+impl Eeprom<'_> {
+    const MAGIC: [u8; 4] = *b"CURI";
+    const VERSION: u8 = 1;
+    // Magic, version, address bytes and log2 of the part size.
+    const HEADER_SIZE: usize = 7;
+    // Address polls to wait out the internal write cycle (5ms max).
+    const WRITE_POLLS: usize = 1_000;
+
+    fn wait_ready(i2c: &mut I2cDev) {
+        for _ in 0..Self::WRITE_POLLS {
+            if i2c.read(Part::ADDRESS, &mut [0]).is_ok() {
+                return;
+            }
+        }
+    }
+
+    // A formatted part describes itself in its header, so it is never probed
+    // again. Anything else is probed, and formatted with the settings copied
+    // over from flash.
+    fn open(i2c: &mut I2cDev, flash: &mut dyn Storage) -> Option<Part> {
+        if let Some(part) = Self::read_header(i2c) {
+            return Some(part);
+        }
+        let part = Part::detect(i2c)?;
+        let mut eeprom = Eeprom { i2c, part };
+        eeprom.format(flash);
+        Some(eeprom.part)
+    }
+
+    // Reading with either address width is harmless on both kinds, and only
+    // the width the header was written with finds it at address zero.
+    fn read_header(i2c: &mut I2cDev) -> Option<Part> {
+        for wide in [false, true] {
+            let part = Part {
+                size: Part::MIN_SIZE,
+                wide,
+            };
+            let mut header = [0; Self::HEADER_SIZE];
+            Eeprom { i2c, part }.read_at(0, &mut header);
+            let [m0, m1, m2, m3, version, width, size] = header;
+            if [m0, m1, m2, m3] == Self::MAGIC
+                && version == Self::VERSION
+                && width == 1 + wide as u8
+                && (10..=16).contains(&size)
+            {
+                return Some(Part {
+                    size: 1 << size,
+                    wide,
+                });
+            }
+        }
+        None
+    }
+
+    // The rest of the header goes in last, so formatting cut short starts
+    // over on the next boot.
+    fn format(&mut self, from: &mut dyn Storage) {
+        self.write_at(0, &Self::MAGIC);
+        self.measure();
+        self.migrate(from);
+        let width = 1 + self.part.wide as u8;
+        let size = self.part.size.ilog2() as u8;
+        self.write_at(4, &[Self::VERSION, width, size]);
+    }
+
+    // Wide parts repeat themselves past their end, so the magic at address
+    // zero shows up again at the part size.
+    fn measure(&mut self) {
+        if !self.part.wide {
+            return;
+        }
+        let mut magic = [0; 4];
+        for size in Part::WIDE_SIZES {
+            self.read_at(size, &mut magic);
+            if magic == Self::MAGIC {
+                self.part.size = size;
+                return;
+            }
+        }
+    }
+
+    fn migrate(&mut self, from: &mut dyn Storage) {
+        let mut buf = [0; Record::CAPACITY];
+        for record in Record::FIXED {
+            let buf = &mut buf[..record.size()];
+            from.read(record, 0, buf);
+            self.write(record, 0, buf);
+        }
+        // Whatever the part held before would not pass for a log.
+        self.write(Record::Log, 0, &Sniffer::new().to_bytes());
+    }
+
+    fn address(&self, addr: usize) -> (u8, [u8; 2], usize) {
+        if self.part.wide {
+            (Part::ADDRESS, (addr as u16).to_be_bytes(), 2)
+        } else {
+            (Part::ADDRESS | (addr >> 8) as u8, [addr as u8, 0], 1)
+        }
+    }
+
+    fn read_at(&mut self, addr: usize, buf: &mut [u8]) {
+        let (dev, header, len) = self.address(addr);
+        if self.i2c.write_read(dev, &header[..len], buf).is_err() {
+            buf.fill(0xff);
+        }
+    }
+
+    fn write_at(&mut self, mut addr: usize, mut data: &[u8]) {
+        let page = self.part.page_size();
+        while !data.is_empty() {
+            let len = data.len().min(page - addr % page);
+            let (dev, header, header_len) = self.address(addr);
+            let mut frame = [0; Part::WIDE_PAGE + 2];
+            frame[..header_len].copy_from_slice(&header[..header_len]);
+            frame[header_len..][..len].copy_from_slice(&data[..len]);
+            if self.i2c.write(dev, &frame[..header_len + len]).is_err() {
+                return;
+            }
+            Self::wait_ready(self.i2c);
+            addr += len;
+            data = &data[len..];
+        }
+    }
+}
+
+impl Storage for Eeprom<'_> {
+    fn read(&mut self, record: Record, offset: usize, buf: &mut [u8]) {
+        if offset + buf.len() > record.size() {
+            buf.fill(0xff);
+            return;
+        }
+        self.read_at(self.part.offset(record) + offset, buf);
+    }
+
+    fn write(&mut self, record: Record, offset: usize, data: &[u8]) {
+        if offset + data.len() <= record.size() {
+            self.write_at(self.part.offset(record) + offset, data);
+        }
+    }
+}
@@ -0,0 +1,88 @@
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum NameTarget {
+    Macro(usize),
+}
+
+pub struct TextEntry {
+    buf: [u8; Self::LEN],
+    cursor: usize,
+    target: NameTarget,
+}
+
+impl TextEntry {
+    pub const LEN: usize = 12;
+    pub const CHARSET: &'static [u8] =
+        b" ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-.";
+
+    pub fn new(target: NameTarget, name: &str) -> Self {
+        let mut buf = [b' '; Self::LEN];
+        for (dst, ch) in buf.iter_mut().zip(name.bytes()) {
+            if Self::CHARSET.contains(&ch) {
+                *dst = ch;
+            }
+        }
+        Self {
+            buf,
+            cursor: 0,
+            target,
+        }
+    }
+
+    pub fn target(&self) -> NameTarget {
+        self.target
+    }
+
+    pub fn text(&self) -> &str {
+        core::str::from_utf8(&self.buf).unwrap_or("")
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.iter().all(|ch| *ch == b' ')
+    }
+
+    pub fn next_char(&mut self, step: u8) {
+        let len = Self::CHARSET.len();
+        self.set_char((self.char_idx() + step as usize % len) % len);
+    }
+
+    pub fn prev_char(&mut self, step: u8) {
+        let len = Self::CHARSET.len();
+        self.set_char((self.char_idx() + len - step as usize % len) % len);
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = (self.cursor + 1).min(Self::LEN - 1);
+    }
+
+    pub fn delete(&mut self) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        // On a blank slot this acts as backspace.
+        if self.cursor > 0 && self.buf[self.cursor] == b' ' {
+            self.cursor -= 1;
+        }
+        self.buf.copy_within(self.cursor + 1.., self.cursor);
+        self.buf[Self::LEN - 1] = b' ';
+        true
+    }
+
+    fn char_idx(&self) -> usize {
+        Self::CHARSET
+            .iter()
+            .position(|ch| *ch == self.buf[self.cursor])
+            .unwrap_or(0)
+    }
+
+    fn set_char(&mut self, idx: usize) {
+        self.buf[self.cursor] = Self::CHARSET[idx];
+    }
+}
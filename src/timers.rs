@@ -0,0 +1,73 @@
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TimerId {
+    RelayLearn,
+}
+
+#[derive(Clone, Copy)]
+pub enum TimerMode {
+    Once(u32),
+}
+
+impl TimerMode {
+    pub fn delay_ms(&self) -> u32 {
+        match self {
+            TimerMode::Once(delay) => *delay,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct SoftTimer {
+    id: TimerId,
+    generation: u8,
+}
+
+pub struct Timers {
+    slots: [Option<SoftTimer>; Self::SLOTS],
+    generation: u8,
+}
+
+impl Timers {
+    pub const SLOTS: usize = 4;
+
+    pub fn new() -> Self {
+        Self {
+            slots: [None; Self::SLOTS],
+            generation: 0,
+        }
+    }
+
+    // Restarting a timer bumps its generation, so expirations scheduled
+    // for the previous run are recognized as stale and dropped.
+    pub fn start(&mut self, id: TimerId) -> Option<u8> {
+        self.cancel(id);
+        let slot = self.slots.iter_mut().find(|slot| slot.is_none())?;
+        self.generation = self.generation.wrapping_add(1);
+        *slot = Some(SoftTimer {
+            id,
+            generation: self.generation,
+        });
+        Some(self.generation)
+    }
+
+    pub fn cancel(&mut self, id: TimerId) {
+        for slot in self.slots.iter_mut() {
+            if matches!(slot, Some(timer) if timer.id == id) {
+                *slot = None;
+            }
+        }
+    }
+
+    pub fn expire(&mut self, id: TimerId, generation: u8) -> bool {
+        let slot = self.slots.iter_mut().find(
+            |slot| matches!(slot, Some(timer) if timer.id == id && timer.generation == generation),
+        );
+        match slot {
+            Some(slot) => {
+                *slot = None;
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use super::*;
+use core::fmt::Write;
+
+const COLS: usize = 20;
+
+pub trait ListSource {
+    fn len(&self) -> usize;
+
+    fn item(&self, idx: usize, out: &mut dyn Write);
+}
+
+pub struct ListView {
+    rows: usize,
+    cursor: usize,
+    offset: usize,
+}
+
+impl ListView {
+    pub fn new(rows: usize) -> Self {
+        Self {
+            rows,
+            cursor: 0,
+            offset: 0,
+        }
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn move_up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+        self.offset = self.offset.min(self.cursor);
+    }
+
+    pub fn move_down(&mut self, len: usize) {
+        if self.cursor + 1 < len {
+            self.cursor += 1;
+        }
+        if self.cursor >= self.offset + self.rows {
+            self.offset = self.cursor + 1 - self.rows;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.cursor = 0;
+        self.offset = 0;
+    }
+}
+
+pub struct ListWidget<const ROWS: usize> {
+    rows: [Text<COLS>; ROWS],
+    scrollbar: [GlyphIcon; ROWS],
+}
+
+impl<const ROWS: usize> ListWidget<ROWS> {
+    pub fn new(origin: Point) -> Self {
+        let row = |idx: usize| Point::new(origin.x, origin.y + idx as u8 * 8);
+        Self {
+            rows: core::array::from_fn(|idx| Text::new(row(idx))),
+            scrollbar: core::array::from_fn(|idx| {
+                let origin = row(idx);
+                GlyphIcon::new(Asset::Scrollbar, 0, Point::new(origin.x + 124, origin.y))
+            }),
+        }
+    }
+
+    fn thumb(view: &ListView, len: usize) -> Option<usize> {
+        if len <= ROWS {
+            return None;
+        }
+        Some(view.offset() * (ROWS - 1) / (len - ROWS))
+    }
+}
+
+impl<const ROWS: usize> Widget<(&ListView, &dyn ListSource)> for ListWidget<ROWS> {
+    fn invalidate(&mut self) {
+        for row in self.rows.iter_mut() {
+            row.invalidate();
+        }
+        for bar in self.scrollbar.iter_mut() {
+            bar.invalidate();
+        }
+    }
+
+    fn update(&mut self, (view, source): (&ListView, &dyn ListSource)) {
+        let len = source.len();
+        let thumb = Self::thumb(view, len);
+        for (idx, (row, bar)) in self.rows.iter_mut().zip(&mut self.scrollbar).enumerate() {
+            let item = view.offset() + idx;
+            let mut line = TextBuf::<COLS>::new();
+            if item < len {
+                let marker = if item == view.cursor() { '>' } else { ' ' };
+                line.write_char(marker).ok();
+                source.item(item, &mut line);
+            }
+            row.update(line.as_str());
+            bar.update(match thumb {
+                Some(thumb) if thumb == idx => 2,
+                Some(_) => 1,
+                None => 0,
+            });
+        }
+    }
+
+    fn render<C: Canvas>(&mut self, canvas: &mut C) {
+        for row in self.rows.iter_mut() {
+            row.render(canvas);
+        }
+        for bar in self.scrollbar.iter_mut() {
+            bar.render(canvas);
+        }
+    }
+}
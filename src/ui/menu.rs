@@ -1,4 +1,5 @@
 use klaptik::*;
+use crate::app::{App, AppRequest, Options};
 use crate::Background;
 use super::*;
 
@@ -14,44 +15,121 @@ widget_group! {
         line3: MenuIcon, Asset::MenuSmall, MenuItem::About, Point::new(24, 48);
     },
     |widget: &mut MenuWidget, state: &Menu| {
-        let mut lines = state.lines.iter().cycle().skip(state.cursor);
+        let mut lines = state.entries().iter().cycle().skip(state.cursor());
         let line = lines.next().unwrap();
-        widget.icon1.update(*line);
-        widget.line1.update(*line);
+        widget.icon1.update(line.icon);
+        widget.line1.update(line.label);
 
         let line = lines.next().unwrap();
-        widget.icon2.update(*line);
-        widget.line2.update(*line);
+        widget.icon2.update(line.icon);
+        widget.line2.update(line.label);
 
         let line = lines.next().unwrap();
-        widget.icon3.update(*line);
-        widget.line3.update(*line);
+        widget.icon3.update(line.icon);
+        widget.line3.update(line.label);
     }
 }
 
-pub struct Menu {
-    lines: &'static [MenuItem],
+#[derive(Clone, Copy)]
+pub enum MenuAction {
+    Open(ViewportNode),
+    Submenu(&'static [MenuEntry]),
+    Toggle(fn(&mut Options) -> AppRequest),
+    Run(fn(&mut App) -> Option<AppRequest>),
+}
+
+#[derive(Clone, Copy)]
+pub struct MenuEntry {
+    pub icon: MenuItem,
+    pub label: MenuItem,
+    pub action: MenuAction,
+}
+
+impl MenuEntry {
+    pub const fn new(item: MenuItem, action: MenuAction) -> Self {
+        Self {
+            icon: item,
+            label: item,
+            action,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct MenuLevel {
+    entries: &'static [MenuEntry],
     cursor: usize,
 }
 
+pub struct Menu {
+    levels: [MenuLevel; Self::DEPTH],
+    depth: usize,
+}
+
 impl Menu {
-    pub fn new(lines: &'static [MenuItem]) -> Self {
-        Self { lines, cursor: 0 }
+    pub const DEPTH: usize = 4;
+
+    pub fn new(entries: &'static [MenuEntry]) -> Self {
+        let root = MenuLevel { entries, cursor: 0 };
+        Self {
+            levels: [root; Self::DEPTH],
+            depth: 1,
+        }
+    }
+
+    pub fn entries(&self) -> &'static [MenuEntry] {
+        self.level().entries
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.level().cursor
     }
 
     pub fn move_up(&mut self) {
-        self.cursor = if self.cursor == 0 {
-            self.lines.len() - 1
+        let len = self.entries().len();
+        let level = self.level_mut();
+        level.cursor = if level.cursor == 0 {
+            len - 1
         } else {
-            self.cursor - 1
+            level.cursor - 1
         }
     }
 
     pub fn move_down(&mut self) {
-        self.cursor = (self.cursor + 1) % self.lines.len();
+        let len = self.entries().len();
+        let level = self.level_mut();
+        level.cursor = (level.cursor + 1) % len;
+    }
+
+    pub fn selected(&self) -> MenuEntry {
+        let entries = self.entries();
+        entries[(self.cursor() + 1) % entries.len()]
+    }
+
+    pub fn enter(&mut self, entries: &'static [MenuEntry]) {
+        if self.depth < Self::DEPTH {
+            self.levels[self.depth] = MenuLevel { entries, cursor: 0 };
+            self.depth += 1;
+        }
+    }
+
+    pub fn leave(&mut self) -> bool {
+        if self.depth == 1 {
+            return false;
+        }
+        self.depth -= 1;
+        true
+    }
+
+    pub fn reset(&mut self) {
+        self.depth = 1;
+    }
+
+    fn level(&self) -> &MenuLevel {
+        &self.levels[self.depth - 1]
     }
 
-    pub fn selected(&self) -> MenuItem {
-        self.lines[(self.cursor + 1) % self.lines.len()]
+    fn level_mut(&mut self) -> &mut MenuLevel {
+        &mut self.levels[self.depth - 1]
     }
 }
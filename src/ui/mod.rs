@@ -2,13 +2,17 @@ use crate::app::App;
 use crate::game::GameUI;
 use klaptik::*;
 
+mod list;
 mod menu;
 mod widgets;
 mod sprites;
+mod text;
 
+pub use list::*;
 pub use menu::*;
 pub use widgets::*;
 pub use sprites::*;
+pub use text::*;
 
 pub type MenuIcon = Icon<MenuItem>;
 pub type SubMenuIcon = Icon<SubMenuItem>;
@@ -23,6 +27,10 @@ pub enum MenuItem {
     Sleep = 5,
     Backlight = 6,
     About = 7,
+    Contrast = 8,
+    Invert = 9,
+    Rotate = 10,
+    Tools = 11,
 }
 
 impl From<MenuItem> for Glyph {
@@ -56,6 +64,10 @@ pub enum Asset {
     GameLogo = 9,
     GamePopup = 10,
     GameBoard = 11,
+    Progress = 12,
+    SmallFont = 13,
+    Lock = 14,
+    Scrollbar = 15,
 }
 
 impl From<Asset> for SpriteId {
@@ -66,25 +78,55 @@ impl From<Asset> for SpriteId {
 
 widget_mux! {
     Viewport<&App>,
-    ViewportNode::MainMenu,
+    ViewportNode::Menu,
     {
-        main_menu: MenuWidget;
-        config_menu: MenuWidget;
+        menu: MenuWidget;
         scan: ScanWidget;
+        relay: RelayWidget;
+        log: LogWidget;
         send: SendWidget;
+        replay: ReplayWidget;
+        timer: TimerWidget;
         backlight: BacklightWidget;
+        contrast: ContrastWidget;
         sleep_timeout: SleepTimeoutWidget;
         about: AboutWidget;
         game: GameUI;
+        name_entry: NameEntryWidget;
+        name_confirm: NameConfirmWidget;
+        battery: BatteryWidget;
+        battery_warning: BatteryWarningWidget;
+        i2c_scan: I2cScanWidget;
     },
     |widget: &mut Viewport, state: &App| {
-        widget.main_menu.update(&state.main_menu);
-        widget.config_menu.update(&state.config_menu);
+        widget.menu.update(&state.menu);
         widget.game.update(&state.game);
-        widget.backlight.update(state.options.backlight);
-        widget.sleep_timeout.update(state.options.sleep_timeout);
+        widget.backlight.update(&state.options);
+        widget.contrast.update(state.options.contrast);
+        widget.sleep_timeout.update(&state.options);
         widget.scan.update(state);
+        widget.relay.update(state);
+        widget.log.update(state);
         widget.send.update(state);
-        widget.set_active(state.active_widget);
+        widget.replay.update(state);
+        widget.timer.update(state);
+        widget.name_entry.update(&state.text_entry);
+        widget.name_confirm.update(&state.text_entry);
+        widget.battery.update(&state.battery);
+        widget.battery_warning.update(&state.battery);
+        widget.i2c_scan.update(state);
+        widget.set_active(state.active_widget());
+    }
+}
+
+widget_group! {
+    Ui<&App>,
+    {
+        viewport: Viewport;
+        lock: GlyphIcon, Asset::Lock, 0, Point::new(80, 0);
+    },
+    |widget: &mut Ui, state: &App| {
+        widget.viewport.update(state);
+        widget.lock.update(state.locked as _);
     }
 }
@@ -1,7 +1,7 @@
 use super::Asset;
 use klaptik::*;
 
-pub const SPRITES: [FlashSprite; 12] = [
+pub const SPRITES: [FlashSprite; 16] = [
     FlashSprite::new(
         Asset::Background as _,
         Glyphs::Single,
@@ -10,7 +10,7 @@ pub const SPRITES: [FlashSprite; 12] = [
     ),
     FlashSprite::new(
         Asset::Icon as _,
-        Glyphs::Sequential(8),
+        Glyphs::Sequential(12),
         Size::new(16, 16),
         include_bytes!("assets/icons.bin"),
     ),
@@ -28,13 +28,13 @@ pub const SPRITES: [FlashSprite; 12] = [
     ),
     FlashSprite::new(
         Asset::MenuSmall as _,
-        Glyphs::Sequential(8),
+        Glyphs::Sequential(12),
         Size::new(56, 16),
         include_bytes!("assets/menu_small.bin"),
     ),
     FlashSprite::new(
         Asset::MenuLarge as _,
-        Glyphs::Sequential(8),
+        Glyphs::Sequential(12),
         Size::new(104, 32),
         include_bytes!("assets/menu_large.bin"),
     ),
@@ -147,4 +147,37 @@ pub const SPRITES: [FlashSprite; 12] = [
             0x03, 0x03, 0x03, 0x03, 0x03, 0x03,
         ],
     ),
+    FlashSprite::new(
+        Asset::Progress as _,
+        Glyphs::Sequential(2),
+        Size::new(8, 8),
+        &[
+            0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x7e, 0x7e, 0x7e, 0x7e, 0x7e, 0x7e,
+            0x7e, 0x42,
+        ],
+    ),
+    FlashSprite::new(
+        Asset::SmallFont as _,
+        Glyphs::Alphabet(
+            b" !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~",
+        ),
+        Size::new(6, 8),
+        include_bytes!("assets/small_font.bin"),
+    ),
+    FlashSprite::new(
+        Asset::Lock as _,
+        Glyphs::Sequential(2),
+        Size::new(6, 8),
+        &[
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf8, 0xfe, 0xd9, 0xd9, 0xfe, 0xf8,
+        ],
+    ),
+    FlashSprite::new(
+        Asset::Scrollbar as _,
+        Glyphs::Sequential(3),
+        Size::new(4, 8),
+        &[
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x55, 0x00, 0x00, 0x00, 0xff, 0xff, 0x00,
+        ],
+    ),
 ];
@@ -0,0 +1,73 @@
+use super::*;
+use core::fmt::{self, Write};
+
+pub struct Text<const N: usize> {
+    label: Label<N>,
+}
+
+impl<const N: usize> Text<N> {
+    pub fn new(origin: Point) -> Self {
+        Self {
+            label: Label::new(Asset::SmallFont, "", origin, Size::new(6, 8)),
+        }
+    }
+
+    // The small font covers printable ASCII; anything else is shown as '?'.
+    fn glyph(ch: char) -> u8 {
+        match ch {
+            ' '..='~' => ch as u8,
+            _ => b'?',
+        }
+    }
+}
+
+impl<const N: usize> Widget<&str> for Text<N> {
+    fn invalidate(&mut self) {
+        self.label.invalidate();
+    }
+
+    fn update(&mut self, text: &str) {
+        let mut buf = [b' '; N];
+        for (dst, ch) in buf.iter_mut().zip(text.chars()) {
+            *dst = Self::glyph(ch);
+        }
+        if let Ok(text) = core::str::from_utf8(&buf) {
+            self.label.write_str(text).ok();
+        }
+    }
+
+    fn render<C: Canvas>(&mut self, canvas: &mut C) {
+        self.label.render(canvas);
+    }
+}
+
+pub struct TextBuf<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> TextBuf<N> {
+    pub fn new() -> Self {
+        Self {
+            buf: [b' '; N],
+            len: 0,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or("")
+    }
+}
+
+impl<const N: usize> Write for TextBuf<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for ch in s.bytes() {
+            if self.len == N {
+                break;
+            }
+            self.buf[self.len] = if ch.is_ascii() { ch } else { b'?' };
+            self.len += 1;
+        }
+        Ok(())
+    }
+}
@@ -1,5 +1,10 @@
 use super::*;
-use crate::app::App;
+use crate::app::{App, Options, Radix};
+use crate::battery::Battery;
+use crate::i2c_scan::I2cScan;
+use crate::macros::MacroState;
+use crate::relay::RelayMode;
+use crate::text_entry::TextEntry;
 use core::fmt::Write;
 
 widget!(
@@ -25,9 +30,96 @@ widget_group! {
         cmd: Label<3>, Asset::Font, "   ", Point::new(72, 40), Size::new(16, 24);
     },
     |widget: &mut ScanWidget, state: &App| {
-        widget.battery.update(state.battery_voltage);
-        write!(widget.addr, "{: >3}", state.rx_cmd.addr).ok();
-        write!(widget.cmd, "{: >3}", state.rx_cmd.cmd).ok();
+        widget.battery.update(state.battery.glyph());
+        write_value(&mut widget.addr, state.options.radix, state.rx_cmd.addr);
+        write_value(&mut widget.cmd, state.options.radix, state.rx_cmd.cmd);
+    }
+}
+
+widget_group! {
+    RelayWidget<&App>,
+    {
+        bg: Background;
+        icon: MenuIcon, Asset::Icon, MenuItem::Scan, Point::zero();
+        title: MenuIcon, Asset::MenuSmall, MenuItem::Scan, Point::new(24, 0);
+        battery: GlyphIcon, Asset::Battery, 0, Point::new(112, 0);
+        counter: Label<5>, Asset::Font, "    0", Point::new(24, 24), Size::new(16, 24);
+        ir_wake: Text<21>, Point::new(0, 48);
+        status: Text<21>, Point::new(0, 56);
+    },
+    |widget: &mut RelayWidget, state: &App| {
+        widget.battery.update(state.battery.glyph());
+        widget.ir_wake.update(if state.options.ir_wake {
+            "Wake on IR: on"
+        } else {
+            "Wake on IR: off"
+        });
+        match state.relay.mode() {
+            RelayMode::Forward => {
+                write!(widget.counter, "{: >5}", state.relay.relayed() % 100_000).ok();
+                widget.status.update("Forwarding");
+            }
+            RelayMode::LearnSource => {
+                write!(widget.counter, "{: >5}", "?").ok();
+                widget.status.update("Press source key");
+            }
+            RelayMode::LearnTarget(_, _) => {
+                write!(widget.counter, "{: >5}", "??").ok();
+                widget.status.update("Press target key");
+            }
+        }
+    }
+}
+
+widget_group! {
+    LogWidget<&App>,
+    {
+        bg: Background;
+        icon: MenuIcon, Asset::Icon, MenuItem::Scan, Point::zero();
+        title: MenuIcon, Asset::MenuSmall, MenuItem::Scan, Point::new(24, 0);
+        position: Label<7>, Asset::SmallFont, "  0/0  ", Point::new(86, 0), Size::new(6, 8);
+        line1: Label<21>, Asset::SmallFont, "", Point::new(0, 16), Size::new(6, 8);
+        line2: Label<21>, Asset::SmallFont, "", Point::new(0, 24), Size::new(6, 8);
+        line3: Label<21>, Asset::SmallFont, "", Point::new(0, 32), Size::new(6, 8);
+        line4: Label<21>, Asset::SmallFont, "", Point::new(0, 40), Size::new(6, 8);
+        line5: Label<21>, Asset::SmallFont, "", Point::new(0, 48), Size::new(6, 8);
+        line6: Label<21>, Asset::SmallFont, "", Point::new(0, 56), Size::new(6, 8);
+    },
+    |widget: &mut LogWidget, state: &App| {
+        let log = &state.sniffer;
+        let position = (log.scroll() + 1).min(log.len());
+        let frozen = if log.is_frozen() { '*' } else { ' ' };
+        write!(widget.position, "{}{: >2}/{: <3}", frozen, position, log.len()).ok();
+
+        let lines = [
+            &mut widget.line1,
+            &mut widget.line2,
+            &mut widget.line3,
+            &mut widget.line4,
+            &mut widget.line5,
+            &mut widget.line6,
+        ];
+        for (idx, line) in lines.into_iter().enumerate() {
+            match log.entry(log.scroll() + idx) {
+                Some(entry) => {
+                    let secs = entry.timestamp / 10 % 10_000;
+                    let tenths = entry.timestamp % 10;
+                    write!(line, "{: >4}.{} {} ", secs, tenths, entry.protocol.name()).ok();
+                    write_value(line, state.options.radix, entry.addr);
+                    write!(line, " ").ok();
+                    write_value(line, state.options.radix, entry.cmd);
+                    write!(line, " ").ok();
+                    if entry.repeats > 0 {
+                        write!(line, "x{}", entry.repeats.min(9)).ok();
+                    } else {
+                        write!(line, "  ").ok();
+                    }
+                }
+                None => {
+                    write!(line, "{: <21}", "").ok();
+                }
+            }
+        }
     }
 }
 
@@ -42,37 +134,147 @@ widget_group! {
         cmd_title: SubMenuIcon, Asset::SubMenu, SubMenuItem::Command, Point::new(72, 24);
         addr: Label<3>, Asset::Font, "000", Point::new(8, 40), Size::new(16, 24);
         cmd: Label<3>, Asset::Font, "000", Point::new(72, 40), Size::new(16, 24);
+        cursor1: GlyphIcon, Asset::SmallFont, b' ', Point::new(29, 32);
+        cursor2: GlyphIcon, Asset::SmallFont, b' ', Point::new(45, 32);
+        cursor3: GlyphIcon, Asset::SmallFont, b' ', Point::new(93, 32);
+        cursor4: GlyphIcon, Asset::SmallFont, b' ', Point::new(109, 32);
     },
     |widget: &mut SendWidget, state: &App| {
-        widget.battery.update(state.battery_voltage);
-        write!(widget.addr, "{: >3}", state.tx_cmd.addr).ok();
-        write!(widget.cmd, "{: >3}", state.tx_cmd.cmd).ok();
+        widget.battery.update(state.battery.glyph());
+        write_value(&mut widget.addr, state.options.radix, state.tx_cmd.addr);
+        write_value(&mut widget.cmd, state.options.radix, state.tx_cmd.cmd);
+
+        let selected = |nibble: u8| {
+            let active = match state.options.radix {
+                Radix::Dec => nibble / 2 == state.send_cursor,
+                Radix::Hex => nibble == state.send_cursor,
+            };
+            if active {
+                b'-'
+            } else {
+                b' '
+            }
+        };
+        widget.cursor1.update(selected(0));
+        widget.cursor2.update(selected(1));
+        widget.cursor3.update(selected(2));
+        widget.cursor4.update(selected(3));
     }
 }
 
+pub type ProgressBar = WrapPanel<16, 16>;
+
 widget_group! {
-    BacklightWidget<u8>,
+    ReplayWidget<&App>,
+    {
+        bg: Background;
+        icon: MenuIcon, Asset::Icon, MenuItem::Replay, Point::zero();
+        title: MenuIcon, Asset::MenuSmall, MenuItem::Replay, Point::new(24, 0);
+        battery: GlyphIcon, Asset::Battery, 0, Point::new(112, 0);
+        name: Text<12>, Point::new(32, 16);
+        slot: Label<1>, Asset::Font, "1", Point::new(8, 24), Size::new(16, 24);
+        info: Label<6>, Asset::Font, "      ", Point::new(32, 24), Size::new(16, 24);
+        progress: ProgressBar, Asset::Progress, "", Point::new(0, 56), Size::new(8, 8);
+    },
+    |widget: &mut ReplayWidget, state: &App| {
+        let macros = &state.macros;
+        let script = macros.script();
+        widget.battery.update(state.battery.glyph());
+        widget.name.update(script.name());
+        write!(widget.slot, "{}", macros.slot() + 1).ok();
+        if macros.state() == MacroState::Recording {
+            write!(widget.info, "{: >5}x", script.len()).ok();
+        } else if script.is_empty() {
+            write!(widget.info, "{: >6}", "?").ok();
+        } else {
+            let duration = script.duration();
+            write!(widget.info, "{: >3}.{}s", duration / 10, duration % 10).ok();
+        }
+        let filled = macros.progress() as usize * 16 / 100;
+        for idx in 0..16 {
+            widget.progress.set_glyph(idx, (idx < filled) as _);
+        }
+    }
+}
+
+widget_group! {
+    TimerWidget<&App>,
+    {
+        bg: Background;
+        icon: MenuIcon, Asset::Icon, MenuItem::Sleep, Point::zero();
+        title: MenuIcon, Asset::MenuSmall, MenuItem::Sleep, Point::new(24, 0);
+        battery: GlyphIcon, Asset::Battery, 0, Point::new(112, 0);
+        slot: Label<1>, Asset::Font, "1", Point::new(8, 24), Size::new(16, 24);
+        remaining: Label<5>, Asset::Font, "     ", Point::new(48, 24), Size::new(16, 24);
+        progress: ProgressBar, Asset::Progress, "", Point::new(0, 56), Size::new(8, 8);
+    },
+    |widget: &mut TimerWidget, state: &App| {
+        let timer = &state.timer;
+        let slot = if timer.is_armed() {
+            timer.slot()
+        } else {
+            state.macros.slot()
+        };
+        widget.battery.update(state.battery.glyph());
+        write!(widget.slot, "{}", slot + 1).ok();
+        write!(widget.remaining, "{: >4}s", timer.remaining_secs()).ok();
+        let filled = timer.progress() as usize * 16 / 100;
+        for idx in 0..16 {
+            widget.progress.set_glyph(idx, (idx < filled) as _);
+        }
+    }
+}
+
+widget_group! {
+    BacklightWidget<&Options>,
     {
         bg: Background;
         icon: MenuIcon, Asset::Icon, MenuItem::Backlight, Point::zero();
         title: MenuIcon, Asset::MenuSmall, MenuItem::Backlight, Point::new(24, 0);
         brightness: Label<4>, Asset::Font, "100%", Point::new(24, 24), Size::new(16, 24);
+        dim: Text<13>, Point::new(25, 56);
     },
-    |widget: &mut BacklightWidget, brightness: u8| {
-        write!(widget.brightness, "{: >3}%",  brightness * 10).unwrap();
+    |widget: &mut BacklightWidget, options: &Options| {
+        write!(widget.brightness, "{: >3}%",  options.backlight * 10).unwrap();
+        let mut label = TextBuf::<11>::new();
+        match options.dim_timeout {
+            0 => write!(label, "No dimming"),
+            secs => write!(label, "Dim in {secs}s"),
+        }
+        .ok();
+        let mut dim = TextBuf::<13>::new();
+        write!(dim, "<{: ^11}>", label.as_str()).ok();
+        widget.dim.update(dim.as_str());
     }
 }
 
 widget_group! {
-    SleepTimeoutWidget<u8>,
+    ContrastWidget<u8>,
+    {
+        bg: Background;
+        icon: MenuIcon, Asset::Icon, MenuItem::Contrast, Point::zero();
+        title: MenuIcon, Asset::MenuSmall, MenuItem::Contrast, Point::new(24, 0);
+        contrast: Label<3>, Asset::Font, " 31", Point::new(40, 24), Size::new(16, 24);
+    },
+    |widget: &mut ContrastWidget, contrast: u8| {
+        write!(widget.contrast, "{: >3}", contrast).unwrap();
+    }
+}
+
+widget_group! {
+    SleepTimeoutWidget<&Options>,
     {
         bg: Background;
         icon: MenuIcon, Asset::Icon, MenuItem::Sleep, Point::zero();
         title: MenuIcon, Asset::MenuSmall, MenuItem::Sleep, Point::new(24, 0);
         value: Label<3>, Asset::Font, "10s", Point::new(32, 24), Size::new(16, 24);
+        profile: Text<13>, Point::new(25, 56);
     },
-    |widget: &mut SleepTimeoutWidget, timeout: u8| {
-        write!(widget.value, "{timeout}s").unwrap();
+    |widget: &mut SleepTimeoutWidget, options: &Options| {
+        write!(widget.value, "{}s", options.sleep_timeout).unwrap();
+        let mut profile = TextBuf::<13>::new();
+        write!(profile, "<{: ^11}>", options.power.name()).ok();
+        widget.profile.update(profile.as_str());
     }
 }
 
@@ -86,3 +288,107 @@ widget_group! {
         website: GlyphIcon, Asset::Website, 0, Point::new(24, 56);
     }
 }
+
+widget_group! {
+    NameEntryWidget<&TextEntry>,
+    {
+        bg: Background;
+        icon: MenuIcon, Asset::Icon, MenuItem::Replay, Point::zero();
+        title: MenuIcon, Asset::MenuSmall, MenuItem::Replay, Point::new(24, 0);
+        name: Text<{ TextEntry::LEN }>, Point::new(28, 24);
+        cursor: Text<{ TextEntry::LEN }>, Point::new(28, 32);
+        help: Text<21>, Point::new(0, 56);
+    },
+    |widget: &mut NameEntryWidget, entry: &TextEntry| {
+        let mut cursor = [b' '; TextEntry::LEN];
+        cursor[entry.cursor()] = b'^';
+        widget.name.update(entry.text());
+        widget.cursor.update(core::str::from_utf8(&cursor).unwrap_or(""));
+        widget.help.update("A:OK B:DEL UP/DN:CHAR");
+    }
+}
+
+widget_group! {
+    NameConfirmWidget<&TextEntry>,
+    {
+        bg: Background;
+        icon: MenuIcon, Asset::Icon, MenuItem::Replay, Point::zero();
+        title: MenuIcon, Asset::MenuSmall, MenuItem::Replay, Point::new(24, 0);
+        prompt: Text<10>, Point::new(34, 16);
+        name: Text<{ TextEntry::LEN }>, Point::new(28, 32);
+        help: Text<21>, Point::new(0, 56);
+    },
+    |widget: &mut NameConfirmWidget, entry: &TextEntry| {
+        widget.prompt.update("Save name?");
+        widget.name.update(entry.text());
+        widget.help.update("A:SAVE B:EDIT");
+    }
+}
+
+widget_group! {
+    BatteryWidget<&Battery>,
+    {
+        bg: Background;
+        icon: MenuIcon, Asset::Icon, MenuItem::Curio, Point::zero();
+        title: MenuIcon, Asset::MenuSmall, MenuItem::Curio, Point::new(24, 0);
+        battery: GlyphIcon, Asset::Battery, 0, Point::new(112, 0);
+        chemistry: Text<6>, Point::new(24, 16);
+        percent: Label<4>, Asset::Font, "  0%", Point::new(32, 24), Size::new(16, 24);
+        voltage: Label<7>, Asset::SmallFont, "", Point::new(43, 56), Size::new(6, 8);
+    },
+    |widget: &mut BatteryWidget, battery: &Battery| {
+        widget.battery.update(battery.glyph());
+        widget.chemistry.update(battery.chemistry().name());
+        write!(widget.percent, "{: >3}%", battery.percent()).ok();
+        write!(widget.voltage, "{: >4} mV", battery.millivolts()).ok();
+    }
+}
+
+widget_group! {
+    BatteryWarningWidget<&Battery>,
+    {
+        bg: Background;
+        battery: GlyphIcon, Asset::Battery, 0, Point::new(56, 8);
+        title: Text<11>, Point::new(31, 32);
+        voltage: Label<7>, Asset::SmallFont, "", Point::new(43, 40), Size::new(6, 8);
+        help: Text<13>, Point::new(25, 56);
+    },
+    |widget: &mut BatteryWarningWidget, battery: &Battery| {
+        widget.battery.update(battery.glyph());
+        widget.title.update("Low battery");
+        write!(widget.voltage, "{: >4} mV", battery.millivolts()).ok();
+        widget.help.update("Press any key");
+    }
+}
+
+widget_group! {
+    I2cScanWidget<&App>,
+    {
+        bg: Background;
+        icon: MenuIcon, Asset::Icon, MenuItem::Tools, Point::zero();
+        title: MenuIcon, Asset::MenuSmall, MenuItem::Tools, Point::new(24, 0);
+        battery: GlyphIcon, Asset::Battery, 0, Point::new(112, 0);
+        status: Text<21>, Point::new(0, 16);
+        list: ListWidget<{ I2cScan::ROWS }>, Point::new(0, 24);
+    },
+    |widget: &mut I2cScanWidget, state: &App| {
+        widget.battery.update(state.battery.glyph());
+        let scan = &state.i2c_scan;
+        let mut status = TextBuf::<21>::new();
+        if scan.is_scanning() {
+            write!(status, "Scanning...").ok();
+        } else {
+            write!(status, "Found: {}", scan.len()).ok();
+        }
+        widget.status.update(status.as_str());
+        widget.list.update((&state.i2c_view, scan as &dyn ListSource));
+    }
+}
+
+fn write_value<W: Write>(out: &mut W, radix: Radix, value: u8) {
+    match radix {
+        Radix::Dec => write!(out, "{: >3}", value),
+        Radix::Hex => write!(out, "x{:02X}", value),
+    }
+    .ok();
+}
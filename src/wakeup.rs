@@ -0,0 +1,58 @@
+use curio_bsp::hal::rcc::Rcc;
+use curio_bsp::stm32::{EXTI, PWR, RTC};
+
+// RTC wakeup timer on the LSI, the one clock that keeps counting in Stop.
+// It lets the idle timeout run out while the core is stopped.
+pub struct Wakeup {
+    rtc: RTC,
+}
+
+impl Wakeup {
+    // RTC events reach the core through direct EXTI line 19.
+    const EXTI_LINE: u32 = 1 << 19;
+
+    pub fn new(rtc: RTC, exti: &mut EXTI, rcc: &mut Rcc) -> Self {
+        let pwr = unsafe { &*PWR::ptr() };
+        // Backup domain write access (DBP), LSI on, RTC clocked from LSI.
+        pwr.cr1.modify(|r, w| unsafe { w.bits(r.bits() | 1 << 8) });
+        rcc.csr.modify(|r, w| unsafe { w.bits(r.bits() | 1) });
+        while rcc.csr.read().bits() & 1 << 1 == 0 {}
+        rcc.bdcr
+            .modify(|r, w| unsafe { w.bits(r.bits() & !(0b11 << 8) | 0b10 << 8 | 1 << 15) });
+        rcc.apbenr1
+            .modify(|r, w| unsafe { w.bits(r.bits() | 1 << 10) });
+        exti.imr1
+            .modify(|r, w| unsafe { w.bits(r.bits() | Self::EXTI_LINE) });
+        Self { rtc }
+    }
+
+    // Raises RTC_TAMP after `secs` seconds of the 1 Hz calendar clock.
+    pub fn start(&mut self, secs: u32) {
+        self.unlocked(|rtc| {
+            rtc.wutr.write(|w| unsafe { w.bits(secs.max(1) - 1) });
+            // WUCKSEL = ck_spre, WUTIE, WUTE.
+            rtc.cr
+                .modify(|r, w| unsafe { w.bits(r.bits() & !0b111 | 0b100 | 1 << 14 | 1 << 10) });
+        });
+    }
+
+    pub fn cancel(&mut self) {
+        self.unlocked(|rtc| {
+            rtc.cr
+                .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << 14 | 1 << 10)) });
+            rtc.scr.write(|w| unsafe { w.bits(1 << 2) });
+        });
+    }
+
+    // The timer only takes a new reload while disabled (WUTWF set).
+    fn unlocked(&mut self, f: impl FnOnce(&RTC)) {
+        let rtc = &self.rtc;
+        rtc.wpr.write(|w| unsafe { w.bits(0xca) });
+        rtc.wpr.write(|w| unsafe { w.bits(0x53) });
+        rtc.cr
+            .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << 10)) });
+        while rtc.icsr.read().bits() & 1 << 2 == 0 {}
+        f(rtc);
+        rtc.wpr.write(|w| unsafe { w.bits(0xff) });
+    }
+}
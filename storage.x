@@ -0,0 +1,4 @@
+/* Settings records take the last three 2 KiB flash pages (29-31, see
+   storage::Record::page), so program text and data must end before them. */
+ASSERT(__sidata + SIZEOF(.data) <= ORIGIN(FLASH) + 29 * 0x800,
+       "firmware overlaps the flash pages reserved for settings");